@@ -0,0 +1,179 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::collate;
+use crate::history::Record;
+
+/// How varied one person's roommates have been across the history.
+#[derive(Debug, Serialize)]
+pub struct PersonStats {
+    /// Their name in the latest run they appear in.
+    pub name: String,
+    pub id: Option<String>,
+    /// How many runs this person appears in.
+    pub runs: usize,
+    pub distinct_partners: usize,
+    /// The most consecutive runs this person spent with the same partner.
+    pub longest_streak: usize,
+    /// Distinct partners per run, from near 0 (always the same partner) to
+    /// 1 (someone new every time).
+    pub diversity: f64,
+}
+
+/// Partner diversity across a whole history, e.g. one term.
+#[derive(Debug, Serialize)]
+pub struct Analytics {
+    pub runs: usize,
+    /// The mean of everyone's diversity.
+    pub diversity_index: f64,
+    pub people: Vec<PersonStats>,
+}
+
+/// How many people share one value of a statistic, or None where some but
+/// fewer than `min_count` do and the count is withheld.
+#[derive(Debug, Serialize)]
+pub struct Bucket {
+    pub value: usize,
+    pub count: Option<usize>,
+}
+
+/// Partner diversity across a history with nothing about any one person:
+/// only totals and counts of people, with any count from 1 to below
+/// `min_count` withheld so that small groups can't be picked out.
+#[derive(Debug, Serialize)]
+pub struct Aggregate {
+    pub runs: usize,
+    pub people: usize,
+    pub min_count: usize,
+    pub diversity_index: f64,
+    /// Rooms of each tier across every run.
+    pub preferred_rooms: Option<usize>,
+    pub accepted_rooms: Option<usize>,
+    pub unpreferred_rooms: Option<usize>,
+    /// How many people had each number of distinct partners.
+    pub distinct_partners: Vec<Bucket>,
+    /// How many people had each longest streak with the same partner.
+    pub longest_streak: Vec<Bucket>,
+}
+
+/// One person's partners so far, as [`Tally`] reads the history.
+#[derive(Default)]
+struct Timeline {
+    name: String,
+    id: Option<String>,
+    runs: usize,
+    partners: HashSet<String>,
+    /// The run they were last in and who they shared with then.
+    last: Option<(usize, Vec<String>)>,
+    streak: usize,
+    longest_streak: usize,
+}
+
+/// Partner diversity built up one record at a time, so a history never
+/// has to be held in memory all at once.
+#[derive(Default)]
+pub struct Tally {
+    runs: usize,
+    preferred_rooms: usize,
+    accepted_rooms: usize,
+    unpreferred_rooms: usize,
+    // Keyed by identity so that renames don't split anyone in two.
+    timelines: BTreeMap<String, Timeline>,
+}
+
+impl Tally {
+    /// Adds the next record, oldest first.
+    pub fn add(&mut self, record: &Record) {
+        let run = self.runs;
+        self.runs += 1;
+        self.preferred_rooms += record.preferred;
+        self.accepted_rooms += record.accepted;
+        self.unpreferred_rooms += record.unpreferred;
+        for room in &record.rooms {
+            for person in &room.people {
+                let mut partners = room
+                    .people
+                    .iter()
+                    .filter(|x| *x != person)
+                    .map(|x| record.identity(x).to_string())
+                    .collect::<Vec<_>>();
+                partners.sort();
+                let timeline = self
+                    .timelines
+                    .entry(record.identity(person).to_string())
+                    .or_default();
+                timeline.name.clone_from(person);
+                if let Some(id) = record.ids.get(person) {
+                    timeline.id = Some(id.clone());
+                }
+                timeline.runs += 1;
+                timeline.streak = match &timeline.last {
+                    Some((previous, x))
+                        if *previous + 1 == run && *x == partners && !partners.is_empty() =>
+                    {
+                        timeline.streak + 1
+                    }
+                    _ if !partners.is_empty() => 1,
+                    _ => 0,
+                };
+                timeline.longest_streak = timeline.longest_streak.max(timeline.streak);
+                timeline.partners.extend(partners.iter().cloned());
+                timeline.last = Some((run, partners));
+            }
+        }
+    }
+
+    pub fn analytics(&self) -> Analytics {
+        let mut people = self
+            .timelines
+            .values()
+            .map(|timeline| PersonStats {
+                name: timeline.name.clone(),
+                id: timeline.id.clone(),
+                runs: timeline.runs,
+                distinct_partners: timeline.partners.len(),
+                longest_streak: timeline.longest_streak,
+                diversity: timeline.partners.len() as f64 / timeline.runs.max(1) as f64,
+            })
+            .collect::<Vec<_>>();
+        people.sort_by(|a, b| collate::compare(&a.name, &b.name));
+
+        Analytics {
+            runs: self.runs,
+            diversity_index: people.iter().map(|x| x.diversity).sum::<f64>()
+                / people.len().max(1) as f64,
+            people,
+        }
+    }
+
+    /// Reduces the analytics to totals and counts, withholding any count
+    /// from 1 to below `min_count`.
+    pub fn aggregate(&self, min_count: usize) -> Aggregate {
+        let analytics = self.analytics();
+        let keep = |count: usize| (count == 0 || count >= min_count).then_some(count);
+        let buckets = |value: fn(&PersonStats) -> usize| {
+            let mut counts = BTreeMap::<usize, usize>::new();
+            for person in &analytics.people {
+                *counts.entry(value(person)).or_default() += 1;
+            }
+            counts
+                .into_iter()
+                .map(|(value, count)| Bucket {
+                    value,
+                    count: keep(count),
+                })
+                .collect()
+        };
+        Aggregate {
+            runs: analytics.runs,
+            people: analytics.people.len(),
+            min_count,
+            diversity_index: analytics.diversity_index,
+            preferred_rooms: keep(self.preferred_rooms),
+            accepted_rooms: keep(self.accepted_rooms),
+            unpreferred_rooms: keep(self.unpreferred_rooms),
+            distinct_partners: buckets(|x| x.distinct_partners),
+            longest_streak: buckets(|x| x.longest_streak),
+        }
+    }
+}
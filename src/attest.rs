@@ -0,0 +1,140 @@
+//! Signed attestations for published rosters: `attest` signs a file
+//! (e.g. a solution written by `--save`) with an Ed25519 key, and
+//! `verify-attestation` later proves it hasn't changed since, so a copy
+//! edited after publishing can be told apart from the real one.
+
+use anyhow::{anyhow, bail, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The schema version of attestations written by this build.
+pub const SCHEMA_VERSION: i64 = 1;
+
+/// How the DER of an Ed25519 `PUBLIC KEY` starts; the key follows it.
+const SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// A signature over a file's contents, written next to it.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Attestation {
+    /// [`SCHEMA_VERSION`] when the attestation was written.
+    pub schema_version: i64,
+    /// The file's name when it was signed, for reference.
+    pub file: String,
+    /// SHA-256 of the file's contents, in hex.
+    pub sha256: String,
+    /// The signer's Ed25519 public key, in base64.
+    pub public_key: String,
+    /// The Ed25519 signature of the file's contents, in base64.
+    pub signature: String,
+}
+
+/// Where the attestation for `path` goes unless told otherwise.
+pub fn default_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".attestation.json");
+    name.into()
+}
+
+fn hash(contents: &[u8]) -> String {
+    Sha256::digest(contents)
+        .iter()
+        .map(|x| format!("{x:02x}"))
+        .collect()
+}
+
+/// The label and DER contents of the first PEM block in `text`.
+fn read_pem(text: &str, path: &Path) -> Result<(String, Vec<u8>)> {
+    let invalid = || anyhow!("{} isn't a PEM key file", path.display());
+    let mut lines = text.lines().map(str::trim);
+    let label = lines
+        .find_map(|x| x.strip_prefix("-----BEGIN ")?.strip_suffix("-----"))
+        .ok_or_else(invalid)?;
+    let end = format!("-----END {label}-----");
+    let body = lines
+        .take_while(|x| *x != end)
+        .filter(|x| !x.contains(':'))
+        .collect::<String>();
+    let der = STANDARD.decode(body).map_err(|_| invalid())?;
+    Ok((label.to_string(), der))
+}
+
+fn key_pair(der: &[u8], path: &Path) -> Result<Ed25519KeyPair> {
+    Ed25519KeyPair::from_pkcs8_maybe_unchecked(der)
+        .map_err(|_| anyhow!("{} isn't an Ed25519 private key", path.display()))
+}
+
+/// Signs the contents of `file` with the Ed25519 private key (PKCS#8,
+/// e.g. from `openssl genpkey -algorithm ed25519`) in `key`.
+pub fn attest(file: &Path, key: &Path) -> Result<Attestation> {
+    let (label, der) = read_pem(&fs::read_to_string(key)?, key)?;
+    if label != "PRIVATE KEY" {
+        bail!(
+            "{} holds a {}, but signing needs a private key",
+            key.display(),
+            label.to_lowercase()
+        );
+    }
+    let pair = key_pair(&der, key)?;
+    let contents = fs::read(file)?;
+    Ok(Attestation {
+        schema_version: SCHEMA_VERSION,
+        file: file
+            .file_name()
+            .map_or_else(String::new, |x| x.to_string_lossy().into_owned()),
+        sha256: hash(&contents),
+        public_key: STANDARD.encode(pair.public_key()),
+        signature: STANDARD.encode(pair.sign(&contents)),
+    })
+}
+
+/// Makes sure `file` is exactly what `attestation` signed, and that it was
+/// signed with the key in `key`: the public key, or the private key it
+/// belongs to.
+pub fn verify(file: &Path, attestation: &Attestation, key: &Path) -> Result<()> {
+    if attestation.schema_version > SCHEMA_VERSION {
+        bail!(
+            "the attestation uses schema version {}, but this build only understands up to {SCHEMA_VERSION}",
+            attestation.schema_version
+        );
+    }
+    let (label, der) = read_pem(&fs::read_to_string(key)?, key)?;
+    let public_key = match label.as_str() {
+        "PUBLIC KEY" => match der.strip_prefix(&SPKI_PREFIX[..]) {
+            Some(key) if key.len() == 32 => key.to_vec(),
+            _ => bail!("{} isn't an Ed25519 public key", key.display()),
+        },
+        "PRIVATE KEY" => key_pair(&der, key)?.public_key().as_ref().to_vec(),
+        _ => bail!(
+            "{} holds a {}, not a key",
+            key.display(),
+            label.to_lowercase()
+        ),
+    };
+    if STANDARD.encode(&public_key) != attestation.public_key {
+        bail!(
+            "{} was signed with a different key than the one in {}",
+            file.display(),
+            key.display()
+        );
+    }
+    let contents = fs::read(file)?;
+    let signature = STANDARD
+        .decode(&attestation.signature)
+        .map_err(|_| anyhow!("the attestation's signature isn't valid base64"))?;
+    if hash(&contents) != attestation.sha256
+        || UnparsedPublicKey::new(&ED25519, &public_key)
+            .verify(&contents, &signature)
+            .is_err()
+    {
+        bail!("{} has changed since it was signed", file.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+//! `solver = "auto"`: picks a strategy from the size and shape of the
+//! config, so that getting good rooms doesn't take knowing how each solver
+//! works.
+
+use crate::components::preference_components;
+use crate::config::Strategy;
+use crate::exact;
+use crate::solver::{contract, tier, Problem, Tier};
+
+/// The strategy picked for a config, and why, for the log.
+pub struct Choice {
+    pub strategy: Strategy,
+    pub reason: String,
+}
+
+/// The share of all possible pairs among `people` who prefer each other.
+fn density(people: &[String], problem: &Problem) -> f64 {
+    let n = people.len();
+    if n < 2 {
+        return 0.0;
+    }
+    let mutual = people
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| people[i + 1..].iter().map(move |b| (a, b)))
+        .filter(|(a, b)| tier(a, b, problem) == Tier::Preferred)
+        .count();
+    mutual as f64 / (n * (n - 1) / 2) as f64
+}
+
+/// Solves exactly when there are few enough people for the exact solver,
+/// samples each cluster of mutual preferences on its own when they fall
+/// into several small ones, and samples everyone together otherwise.
+pub fn choose(problem: &Problem) -> Choice {
+    let (_, free) = contract(problem);
+    let n = free.len();
+    if exact::check(problem).is_ok() {
+        return Choice {
+            strategy: Strategy::Exact,
+            reason: format!(
+                "{n} people are left to pair, few enough to find the best pairing exactly"
+            ),
+        };
+    }
+    let clusters = preference_components(problem)
+        .into_iter()
+        .filter(|x| x.len() > 1)
+        .collect::<Vec<_>>();
+    let largest = clusters.first().map_or(0, Vec::len);
+    let density = density(&free, problem);
+    if clusters.len() >= 3 && largest * 3 <= n {
+        return Choice {
+            strategy: Strategy::Components,
+            reason: format!(
+                "{n} people fall into {} clusters of mutual preferences, the largest of {largest}, which are best solved one at a time",
+                clusters.len()
+            ),
+        };
+    }
+    let shape = match clusters.len() {
+        0 => "no mutual preferences at all".to_string(),
+        1 => "a single cluster of mutual preferences".to_string(),
+        2 => "only two clusters of mutual preferences".to_string(),
+        _ => format!("one cluster of mutual preferences holding {largest} of them"),
+    };
+    Choice {
+        strategy: Strategy::Sample,
+        reason: format!(
+            "{n} people with {:.1}% of pairs preferring each other and {shape} are best sampled together",
+            density * 100.0
+        ),
+    }
+}
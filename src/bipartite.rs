@@ -0,0 +1,121 @@
+use anyhow::{bail, Result};
+
+use crate::config::Ranking;
+use crate::hungarian;
+use crate::solver::{allowed, contract, pair_score, tier, Problem, Solution, Tier};
+
+/// Splits `people` into the two groups named in their `group` field.
+fn split_groups<'a>(
+    problem: &Problem,
+    people: &'a [String],
+) -> Result<(Vec<&'a String>, Vec<&'a String>)> {
+    let mut labels = problem.groups.values().collect::<Vec<_>>();
+    labels.sort();
+    labels.dedup();
+    if labels.len() != 2 {
+        bail!(
+            "bipartite mode needs exactly two groups, found {}",
+            labels.len()
+        );
+    }
+    if let Some(name) = problem
+        .people
+        .iter()
+        .find(|x| !problem.groups.contains_key(*x))
+    {
+        bail!("{name} has no group, which bipartite mode requires");
+    }
+    let (first, second): (Vec<_>, Vec<_>) = people
+        .iter()
+        .partition(|x| &problem.groups[*x] == labels[0]);
+    if first.len() != second.len() {
+        bail!(
+            "bipartite mode needs groups of equal size, but {} has {} people and {} has {}",
+            labels[0],
+            first.len(),
+            labels[1],
+            second.len()
+        );
+    }
+    Ok((first, second))
+}
+
+/// The weight of a room's tier, before scaling.
+fn tier_weight(tier: Tier, preferred: f64, accepted: f64) -> f64 {
+    match tier {
+        Tier::Preferred => preferred,
+        Tier::Accepted => accepted,
+        Tier::Unpreferred => 0.0,
+    }
+}
+
+/// Finds the best possible set of rooms where every room holds one person
+/// from each group. Counters are maximised in the order set by `ranking`,
+/// so the result is an exact optimum rather than the best of a sample.
+pub fn solve(problem: &Problem) -> Result<Solution> {
+    let (locked, free) = contract(problem);
+    if let Some((a, b)) = locked.iter().find(|(a, b)| {
+        matches!((problem.groups.get(a), problem.groups.get(b)), (Some(x), Some(y)) if x == y)
+    }) {
+        bail!("{a} and {b} are pinned to the same room, but bipartite mode needs them in different groups");
+    }
+    let (first, second) = split_groups(problem, &free)?;
+    let rooms = first.len() as f64;
+
+    // Scale each counter so that no amount of gains in a lower-ranked one
+    // can outweigh one step in a higher-ranked one.
+    let max_soft = first
+        .iter()
+        .flat_map(|a| second.iter().map(move |b| pair_score(a, b, problem).abs()))
+        .fold(0.0, f64::max);
+    let soft_scale = 2.0 * rooms * max_soft + 1.0;
+    let weight = |a: &str, b: &str| {
+        let tier = tier(a, b, problem);
+        let soft = pair_score(a, b, problem);
+        match problem.settings.ranking {
+            Ranking::Preferred => tier_weight(tier, rooms + 1.0, 1.0) * soft_scale + soft,
+            Ranking::Avoids => tier_weight(tier, rooms + 2.0, rooms + 1.0) * soft_scale + soft,
+            // Soft scores aren't whole numbers in general, so this is only
+            // exact when differences in them are at least 1.
+            Ranking::Score => {
+                soft * (rooms * (rooms + 1.0) + 1.0) + tier_weight(tier, rooms + 1.0, 1.0)
+            }
+        }
+    };
+    let max_weight = first
+        .iter()
+        .flat_map(|a| second.iter().map(|b| weight(a, b).abs()))
+        .fold(0.0, f64::max);
+
+    // Pairs that would break a room pin cost more than every allowed
+    // assignment put together, so they are only used if nothing else works.
+    let forbidden = 2.0 * (rooms + 1.0) * max_weight + 1.0;
+    let cost = first
+        .iter()
+        .map(|a| {
+            second
+                .iter()
+                .map(|b| {
+                    if allowed(a, b, problem) {
+                        -weight(a, b)
+                    } else {
+                        forbidden
+                    }
+                })
+                .collect()
+        })
+        .collect::<Vec<Vec<f64>>>();
+    let assignment = hungarian::solve(&cost);
+
+    let mut result = locked;
+    result.extend(
+        first
+            .iter()
+            .zip(assignment)
+            .map(|(a, b)| ((*a).clone(), second[b].clone())),
+    );
+    if let Some((a, b)) = result.iter().find(|(a, b)| !allowed(a, b, problem)) {
+        bail!("room pins and vetoes can't be honoured in bipartite mode: {a} and {b} would have to share");
+    }
+    Ok(Solution::from_rooms(result, problem))
+}
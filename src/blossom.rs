@@ -0,0 +1,783 @@
+//! Maximum-weight matching in a general graph, by Edmonds' blossom
+//! algorithm with dual variables, in O(n³). People are vertices and every
+//! pair who may share is an edge, so unlike [`crate::hungarian`] it isn't
+//! limited to matching one group against another.
+//!
+//! Weights are compared the way [`crate::solver::ranking_key`] compares
+//! counters, part by part, so the matching found is the best under the
+//! ranking rather than under some blend of its counters.
+
+use std::ops::{Add, Sub};
+
+/// A weight compared by its first part, then its second, then its third.
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+struct Weight([f64; 3]);
+
+impl Add for Weight {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self([0, 1, 2].map(|i| self.0[i] + other.0[i]))
+    }
+}
+
+impl Sub for Weight {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self([0, 1, 2].map(|i| self.0[i] - other.0[i]))
+    }
+}
+
+impl Weight {
+    fn half(self) -> Self {
+        Self(self.0.map(|x| x / 2.0))
+    }
+}
+
+/// No vertex, edge end or blossom.
+const NONE: usize = usize::MAX;
+
+/// Labels in the alternating forest grown from single vertices.
+const FREE: u8 = 0;
+const OUTER: u8 = 1;
+const INNER: u8 = 2;
+/// Set on outer blossoms already passed while looking for a common base.
+const SEEN: u8 = 4;
+
+/// Every index below runs over vertices `0..n` and blossoms `n..2n`, so
+/// that a vertex can stand for the trivial blossom holding only itself.
+/// Edge `k` has ends `2k` and `2k + 1`; `end ^ 1` is the other end.
+struct Matching {
+    n: usize,
+    edges: Vec<(usize, usize, Weight)>,
+    /// The vertex at each end.
+    endpoint: Vec<usize>,
+    /// The far end of each edge at each vertex.
+    neighbours: Vec<Vec<usize>>,
+    /// The far end of the matched edge at each vertex.
+    mate: Vec<usize>,
+    label: Vec<u8>,
+    /// The end through which each labelled vertex or top-level blossom
+    /// got its label.
+    label_end: Vec<usize>,
+    /// The top-level blossom each vertex is in.
+    in_blossom: Vec<usize>,
+    parent: Vec<usize>,
+    /// The sub-blossoms of each blossom, starting at its base and going
+    /// round the cycle.
+    children: Vec<Vec<usize>>,
+    base: Vec<usize>,
+    /// The ends of the edges joining each blossom's sub-blossoms, with
+    /// `ends[b][i]` going from `children[b][i]` to the next.
+    ends: Vec<Vec<usize>>,
+    /// The edge of least slack from each free vertex or outer blossom to
+    /// a different outer blossom.
+    best_edge: Vec<usize>,
+    /// For outer blossoms, the edge of least slack to each other outer
+    /// blossom, so `best_edge` can be worked out when blossoms merge.
+    best_edges: Vec<Option<Vec<usize>>>,
+    unused: Vec<usize>,
+    dual: Vec<Weight>,
+    /// Edges known to have no slack.
+    tight: Vec<bool>,
+    /// Outer vertices whose edges are still to be scanned.
+    queue: Vec<usize>,
+}
+
+impl Matching {
+    fn slack(&self, k: usize) -> Weight {
+        let (i, j, weight) = self.edges[k];
+        self.dual[i] + self.dual[j] - weight - weight
+    }
+
+    fn leaves(&self, b: usize) -> Vec<usize> {
+        if b < self.n {
+            return vec![b];
+        }
+        self.children[b]
+            .iter()
+            .flat_map(|x| self.leaves(*x))
+            .collect()
+    }
+
+    /// Labels `w` and its top-level blossom, reached through end `p`; an
+    /// inner blossom's mate becomes outer in turn.
+    fn assign_label(&mut self, w: usize, label: u8, p: usize) {
+        let b = self.in_blossom[w];
+        self.label[w] = label;
+        self.label[b] = label;
+        self.label_end[w] = p;
+        self.label_end[b] = p;
+        self.best_edge[w] = NONE;
+        self.best_edge[b] = NONE;
+        if label == OUTER {
+            let leaves = self.leaves(b);
+            self.queue.extend(leaves);
+        } else {
+            let mate = self.mate[self.base[b]];
+            self.assign_label(self.endpoint[mate], OUTER, mate ^ 1);
+        }
+    }
+
+    /// Follows the alternating paths back from outer vertices `v` and `w`
+    /// at once, returning the base of the blossom they close or `NONE` if
+    /// they reach different single vertices, making an augmenting path.
+    fn scan_blossom(&mut self, mut v: usize, mut w: usize) -> usize {
+        let mut path = vec![];
+        let mut base = NONE;
+        while v != NONE || w != NONE {
+            let b = self.in_blossom[v];
+            if self.label[b] & SEEN != 0 {
+                base = self.base[b];
+                break;
+            }
+            path.push(b);
+            self.label[b] = OUTER | SEEN;
+            if self.label_end[b] == NONE {
+                v = NONE;
+            } else {
+                v = self.endpoint[self.label_end[b]];
+                let b = self.in_blossom[v];
+                v = self.endpoint[self.label_end[b]];
+            }
+            if w != NONE {
+                (v, w) = (w, v);
+            }
+        }
+        for b in path {
+            self.label[b] = OUTER;
+        }
+        base
+    }
+
+    /// Makes a new outer blossom with `base` of the cycle that edge `k`
+    /// closes.
+    fn add_blossom(&mut self, base: usize, k: usize) {
+        let (mut v, mut w, _) = self.edges[k];
+        let bb = self.in_blossom[base];
+        let mut bv = self.in_blossom[v];
+        let mut bw = self.in_blossom[w];
+        let b = self
+            .unused
+            .pop()
+            .expect("there are never more than n blossoms");
+        self.base[b] = base;
+        self.parent[b] = NONE;
+        self.parent[bb] = b;
+        let mut children = vec![];
+        let mut ends = vec![];
+        while bv != bb {
+            self.parent[bv] = b;
+            children.push(bv);
+            ends.push(self.label_end[bv]);
+            v = self.endpoint[self.label_end[bv]];
+            bv = self.in_blossom[v];
+        }
+        children.push(bb);
+        children.reverse();
+        ends.reverse();
+        ends.push(2 * k);
+        while bw != bb {
+            self.parent[bw] = b;
+            children.push(bw);
+            ends.push(self.label_end[bw] ^ 1);
+            w = self.endpoint[self.label_end[bw]];
+            bw = self.in_blossom[w];
+        }
+        self.children[b] = children.clone();
+        self.ends[b] = ends;
+        self.label[b] = OUTER;
+        self.label_end[b] = self.label_end[bb];
+        self.dual[b] = Weight::default();
+        for v in self.leaves(b) {
+            // Inner vertices become outer as part of the blossom.
+            if self.label[self.in_blossom[v]] == INNER {
+                self.queue.push(v);
+            }
+            self.in_blossom[v] = b;
+        }
+
+        let mut best_to = vec![NONE; 2 * self.n];
+        for bv in children {
+            let lists = match self.best_edges[bv].take() {
+                Some(list) => vec![list],
+                None => self
+                    .leaves(bv)
+                    .iter()
+                    .map(|v| self.neighbours[*v].iter().map(|p| p / 2).collect())
+                    .collect(),
+            };
+            for k in lists.into_iter().flatten() {
+                let (i, j, _) = self.edges[k];
+                let j = if self.in_blossom[j] == b { i } else { j };
+                let bj = self.in_blossom[j];
+                if bj != b
+                    && self.label[bj] == OUTER
+                    && (best_to[bj] == NONE || self.slack(k) < self.slack(best_to[bj]))
+                {
+                    best_to[bj] = k;
+                }
+            }
+            self.best_edge[bv] = NONE;
+        }
+        let list = best_to
+            .into_iter()
+            .filter(|x| *x != NONE)
+            .collect::<Vec<_>>();
+        self.best_edge[b] = NONE;
+        for &k in &list {
+            if self.best_edge[b] == NONE || self.slack(k) < self.slack(self.best_edge[b]) {
+                self.best_edge[b] = k;
+            }
+        }
+        self.best_edges[b] = Some(list);
+    }
+
+    /// Breaks blossom `b` back into its sub-blossoms, relabelling the ones
+    /// on the path through it if it was inner. At the end of a stage, any
+    /// sub-blossoms with a dual of zero are broken up too.
+    fn expand_blossom(&mut self, b: usize, end_stage: bool) {
+        for s in self.children[b].clone() {
+            self.parent[s] = NONE;
+            if s < self.n {
+                self.in_blossom[s] = s;
+            } else if end_stage && self.dual[s] == Weight::default() {
+                self.expand_blossom(s, end_stage);
+            } else {
+                for v in self.leaves(s) {
+                    self.in_blossom[v] = s;
+                }
+            }
+        }
+        if !end_stage && self.label[b] == INNER {
+            let children = self.children[b].clone();
+            let ends = self.ends[b].clone();
+            let len = children.len() as isize;
+            let at = |j: isize| j.rem_euclid(len) as usize;
+            // Go round the blossom from where it was entered to its base,
+            // whichever way the path has an even number of edges.
+            let entry = self.in_blossom[self.endpoint[self.label_end[b] ^ 1]];
+            let mut j = children
+                .iter()
+                .position(|x| *x == entry)
+                .expect("the entry is a sub-blossom") as isize;
+            let (step, trick) = if j & 1 == 1 {
+                j -= len;
+                (1, 0)
+            } else {
+                (-1, 1)
+            };
+            let mut p = self.label_end[b];
+            while j != 0 {
+                self.label[self.endpoint[p ^ 1]] = FREE;
+                self.label[self.endpoint[ends[at(j - trick as isize)] ^ trick ^ 1]] = FREE;
+                self.assign_label(self.endpoint[p ^ 1], INNER, p);
+                self.tight[ends[at(j - trick as isize)] / 2] = true;
+                j += step;
+                p = ends[at(j - trick as isize)] ^ trick;
+                self.tight[p / 2] = true;
+                j += step;
+            }
+            let bv = children[at(j)];
+            let v = self.endpoint[p ^ 1];
+            self.label[v] = INNER;
+            self.label[bv] = INNER;
+            self.label_end[v] = p;
+            self.label_end[bv] = p;
+            self.best_edge[bv] = NONE;
+            // The rest of the sub-blossoms are off the path, so they keep
+            // a label only if one of their vertices was reached on its own.
+            j += step;
+            while children[at(j)] != entry {
+                let bv = children[at(j)];
+                if self.label[bv] != OUTER {
+                    if let Some(v) = self.leaves(bv).into_iter().find(|x| self.label[*x] != FREE) {
+                        self.label[v] = FREE;
+                        self.label[self.endpoint[self.mate[self.base[bv]]]] = FREE;
+                        self.assign_label(v, INNER, self.label_end[v]);
+                    }
+                }
+                j += step;
+            }
+        }
+        self.label[b] = FREE;
+        self.label_end[b] = NONE;
+        self.children[b].clear();
+        self.ends[b].clear();
+        self.base[b] = NONE;
+        self.best_edges[b] = None;
+        self.best_edge[b] = NONE;
+        self.unused.push(b);
+    }
+
+    /// Swaps matched and unmatched edges along the path in blossom `b`
+    /// from vertex `v` to its base, making `v` the new base.
+    fn augment_blossom(&mut self, b: usize, v: usize) {
+        let mut t = v;
+        while self.parent[t] != b {
+            t = self.parent[t];
+        }
+        if t >= self.n {
+            self.augment_blossom(t, v);
+        }
+        let len = self.children[b].len() as isize;
+        let at = |j: isize| j.rem_euclid(len) as usize;
+        let i = self.children[b]
+            .iter()
+            .position(|x| *x == t)
+            .expect("t is a sub-blossom");
+        let mut j = i as isize;
+        let (step, trick) = if j & 1 == 1 {
+            j -= len;
+            (1, 0)
+        } else {
+            (-1, 1)
+        };
+        while j != 0 {
+            j += step;
+            let t = self.children[b][at(j)];
+            let p = self.ends[b][at(j - trick as isize)] ^ trick;
+            if t >= self.n {
+                self.augment_blossom(t, self.endpoint[p]);
+            }
+            j += step;
+            let t = self.children[b][at(j)];
+            if t >= self.n {
+                self.augment_blossom(t, self.endpoint[p ^ 1]);
+            }
+            self.mate[self.endpoint[p]] = p ^ 1;
+            self.mate[self.endpoint[p ^ 1]] = p;
+        }
+        self.children[b].rotate_left(i);
+        self.ends[b].rotate_left(i);
+        self.base[b] = self.base[self.children[b][0]];
+    }
+
+    /// Swaps matched and unmatched edges along the augmenting path through
+    /// edge `k`, growing the matching by one edge.
+    fn augment_matching(&mut self, k: usize) {
+        let (v, w, _) = self.edges[k];
+        for (mut s, mut p) in [(v, 2 * k + 1), (w, 2 * k)] {
+            loop {
+                let bs = self.in_blossom[s];
+                if bs >= self.n {
+                    self.augment_blossom(bs, s);
+                }
+                self.mate[s] = p;
+                if self.label_end[bs] == NONE {
+                    break;
+                }
+                let t = self.endpoint[self.label_end[bs]];
+                let bt = self.in_blossom[t];
+                s = self.endpoint[self.label_end[bt]];
+                let j = self.endpoint[self.label_end[bt] ^ 1];
+                if bt >= self.n {
+                    self.augment_blossom(bt, j);
+                }
+                self.mate[j] = self.label_end[bt];
+                p = self.label_end[bt] ^ 1;
+            }
+        }
+    }
+
+    /// Grows the forest from outer vertex `v`, returning whether the
+    /// matching was augmented.
+    fn scan(&mut self, v: usize) -> bool {
+        for p in self.neighbours[v].clone() {
+            let k = p / 2;
+            let w = self.endpoint[p];
+            if self.in_blossom[v] == self.in_blossom[w] {
+                continue;
+            }
+            let mut slack = Weight::default();
+            if !self.tight[k] {
+                slack = self.slack(k);
+                if slack <= Weight::default() {
+                    self.tight[k] = true;
+                }
+            }
+            let bw = self.in_blossom[w];
+            if self.tight[k] {
+                if self.label[bw] == FREE {
+                    self.assign_label(w, INNER, p ^ 1);
+                } else if self.label[bw] == OUTER {
+                    let base = self.scan_blossom(v, w);
+                    if base == NONE {
+                        self.augment_matching(k);
+                        return true;
+                    }
+                    self.add_blossom(base, k);
+                } else if self.label[w] == FREE {
+                    self.label[w] = INNER;
+                    self.label_end[w] = p ^ 1;
+                }
+            } else if self.label[bw] == OUTER {
+                let b = self.in_blossom[v];
+                if self.best_edge[b] == NONE || slack < self.slack(self.best_edge[b]) {
+                    self.best_edge[b] = k;
+                }
+            } else if self.label[w] == FREE
+                && (self.best_edge[w] == NONE || slack < self.slack(self.best_edge[w]))
+            {
+                self.best_edge[w] = k;
+            }
+        }
+        false
+    }
+}
+
+/// What changing the duals by the least slack makes possible.
+enum Step {
+    /// Nothing more: the matching is as large as it gets.
+    Done,
+    /// This edge from an outer to a free vertex becomes tight.
+    Grow(usize),
+    /// This edge between two outer blossoms becomes tight.
+    Close(usize),
+    /// This inner blossom's dual reaches zero, so it can be broken up.
+    Expand(usize),
+}
+
+/// Matches as many of `n` vertices as the `edges` allow, weighted
+/// `(i, j, weight)`, and of those matchings the one of greatest total
+/// weight. Returns each vertex's mate, if it has one.
+pub fn solve(n: usize, edges: &[(usize, usize, [f64; 3])]) -> Vec<Option<usize>> {
+    let edges = edges
+        .iter()
+        .map(|&(i, j, weight)| (i, j, Weight(weight)))
+        .collect::<Vec<_>>();
+    let mut neighbours = vec![vec![]; n];
+    for (k, (i, j, _)) in edges.iter().enumerate() {
+        neighbours[*i].push(2 * k + 1);
+        neighbours[*j].push(2 * k);
+    }
+    let heaviest = edges
+        .iter()
+        .map(|x| x.2)
+        .fold(Weight::default(), |a, b| if b > a { b } else { a });
+    let mut m = Matching {
+        n,
+        endpoint: edges.iter().flat_map(|(i, j, _)| [*i, *j]).collect(),
+        tight: vec![false; edges.len()],
+        edges,
+        neighbours,
+        mate: vec![NONE; n],
+        label: vec![FREE; 2 * n],
+        label_end: vec![NONE; 2 * n],
+        in_blossom: (0..n).collect(),
+        parent: vec![NONE; 2 * n],
+        children: vec![vec![]; 2 * n],
+        base: (0..n).chain((n..2 * n).map(|_| NONE)).collect(),
+        ends: vec![vec![]; 2 * n],
+        best_edge: vec![NONE; 2 * n],
+        best_edges: vec![None; 2 * n],
+        unused: (n..2 * n).collect(),
+        dual: [vec![heaviest; n], vec![Weight::default(); n]].concat(),
+        queue: vec![],
+    };
+
+    // Each stage either grows the matching by one edge or finds that it
+    // can't grow any more.
+    for _ in 0..n {
+        m.label.fill(FREE);
+        m.best_edge.fill(NONE);
+        m.best_edges[n..].fill(None);
+        m.tight.fill(false);
+        m.queue.clear();
+        for v in 0..n {
+            if m.mate[v] == NONE && m.label[m.in_blossom[v]] == FREE {
+                m.assign_label(v, OUTER, NONE);
+            }
+        }
+        let mut augmented = false;
+        loop {
+            while let Some(v) = m.queue.pop() {
+                if m.scan(v) {
+                    augmented = true;
+                    break;
+                }
+            }
+            if augmented {
+                break;
+            }
+
+            let mut least = None::<(Weight, Step)>;
+            let mut consider = |delta: Weight, step: Step| {
+                if least.as_ref().is_none_or(|x| delta < x.0) {
+                    least = Some((delta, step));
+                }
+            };
+            for v in 0..n {
+                if m.label[m.in_blossom[v]] == FREE && m.best_edge[v] != NONE {
+                    consider(m.slack(m.best_edge[v]), Step::Grow(m.best_edge[v]));
+                }
+            }
+            for b in 0..2 * n {
+                if m.parent[b] == NONE && m.label[b] == OUTER && m.best_edge[b] != NONE {
+                    consider(m.slack(m.best_edge[b]).half(), Step::Close(m.best_edge[b]));
+                }
+            }
+            for b in n..2 * n {
+                if m.base[b] != NONE && m.parent[b] == NONE && m.label[b] == INNER {
+                    consider(m.dual[b], Step::Expand(b));
+                }
+            }
+            let (delta, step) = least.unwrap_or_else(|| {
+                let lowest = m.dual[..n]
+                    .iter()
+                    .copied()
+                    .fold(heaviest, |a, b| if b < a { b } else { a });
+                let zero = Weight::default();
+                (if lowest > zero { lowest } else { zero }, Step::Done)
+            });
+
+            for v in 0..n {
+                match m.label[m.in_blossom[v]] {
+                    OUTER => m.dual[v] = m.dual[v] - delta,
+                    INNER => m.dual[v] = m.dual[v] + delta,
+                    _ => {}
+                }
+            }
+            for b in n..2 * n {
+                if m.base[b] != NONE && m.parent[b] == NONE {
+                    match m.label[b] {
+                        OUTER => m.dual[b] = m.dual[b] + delta,
+                        INNER => m.dual[b] = m.dual[b] - delta,
+                        _ => {}
+                    }
+                }
+            }
+            match step {
+                Step::Done => break,
+                Step::Grow(k) => {
+                    m.tight[k] = true;
+                    let (i, j, _) = m.edges[k];
+                    let i = if m.label[m.in_blossom[i]] == FREE {
+                        j
+                    } else {
+                        i
+                    };
+                    m.queue.push(i);
+                }
+                Step::Close(k) => {
+                    m.tight[k] = true;
+                    m.queue.push(m.edges[k].0);
+                }
+                Step::Expand(b) => m.expand_blossom(b, false),
+            }
+        }
+        if !augmented {
+            break;
+        }
+        for b in n..2 * n {
+            if m.parent[b] == NONE
+                && m.base[b] != NONE
+                && m.label[b] == OUTER
+                && m.dual[b] == Weight::default()
+            {
+                m.expand_blossom(b, true);
+            }
+        }
+    }
+
+    m.mate
+        .iter()
+        .map(|x| (*x != NONE).then(|| m.endpoint[*x]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng as _, SeedableRng};
+
+    use super::{solve, Weight};
+    use crate::rng::Rng;
+
+    type Edges = Vec<(usize, usize, [f64; 3])>;
+
+    /// The number of matched pairs and their total weight.
+    fn value(mates: &[Option<usize>], edges: &Edges) -> (usize, Weight) {
+        let mut value = (0, Weight::default());
+        for (i, mate) in mates.iter().enumerate() {
+            let Some(j) = *mate else {
+                continue;
+            };
+            assert_eq!(mates[j], Some(i), "{i} and {j} aren't each other's mates");
+            if i < j {
+                let edge = edges
+                    .iter()
+                    .find(|x| (x.0, x.1) == (i, j) || (x.0, x.1) == (j, i))
+                    .unwrap_or_else(|| panic!("{i} and {j} are matched without an edge"));
+                value = (value.0 + 1, value.1 + Weight(edge.2));
+            }
+        }
+        value
+    }
+
+    /// The best value of any matching, trying every one: the first vertex
+    /// not yet decided is either left out or matched to a later one.
+    fn brute_force(n: usize, edges: &Edges) -> (usize, Weight) {
+        fn search(
+            i: usize,
+            matched: &mut [bool],
+            weights: &[Vec<Option<Weight>>],
+        ) -> (usize, Weight) {
+            let n = matched.len();
+            let Some(i) = (i..n).find(|x| !matched[*x]) else {
+                return (0, Weight::default());
+            };
+            matched[i] = true;
+            let mut best = search(i + 1, matched, weights);
+            for j in i + 1..n {
+                if let (false, Some(weight)) = (matched[j], weights[i][j]) {
+                    matched[j] = true;
+                    let (count, total) = search(i + 1, matched, weights);
+                    matched[j] = false;
+                    let value = (count + 1, total + weight);
+                    if value > best {
+                        best = value;
+                    }
+                }
+            }
+            matched[i] = false;
+            best
+        }
+
+        let mut weights = vec![vec![None; n]; n];
+        for &(i, j, weight) in edges {
+            weights[i][j] = Some(Weight(weight));
+            weights[j][i] = Some(Weight(weight));
+        }
+        search(0, &mut vec![false; n], &weights)
+    }
+
+    /// A graph of up to `max` vertices with a random share of the possible
+    /// edges. Weights are eighths from a small range, so that sums are exact
+    /// and ties, also between the first parts, are common.
+    fn graph(rng: &mut Rng, max: usize) -> (usize, Edges) {
+        let n = rng.gen_range(0..=max);
+        let density = rng.gen_range(0.0..=1.0);
+        let mut part = |range: i32| f64::from(rng.gen_range(-range..=range)) / 8.0;
+        let mut edges = vec![];
+        for i in 0..n {
+            for j in i + 1..n {
+                let weight = [part(4), part(40), part(40)];
+                edges.push((i, j, weight));
+            }
+        }
+        edges.retain(|_| rng.gen_bool(density));
+        (n, edges)
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = Rng::seed_from_u64(1);
+        for max in [8, 8, 10, 10, 12, 14] {
+            for _ in 0..200 {
+                let (n, edges) = graph(&mut rng, max);
+                let mates = solve(n, &edges);
+                assert_eq!(mates.len(), n);
+                assert_eq!(value(&mates, &edges), brute_force(n, &edges), "{edges:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn leaves_vertices_without_edges_alone() {
+        assert_eq!(solve(0, &[]), vec![]);
+        assert_eq!(solve(1, &[]), vec![None]);
+        assert_eq!(solve(4, &[]), vec![None; 4]);
+        // Only one of the two edges sharing vertex 1 can be used.
+        let edges = vec![(0, 1, [1.0, 0.0, 0.0]), (1, 2, [2.0, 0.0, 0.0])];
+        assert_eq!(solve(4, &edges), vec![None, Some(2), Some(1), None]);
+    }
+
+    #[test]
+    fn prefers_more_pairs_to_heavier_ones() {
+        let edges = vec![
+            (0, 1, [0.0, 0.0, -5.0]),
+            (1, 2, [10.0, 0.0, 0.0]),
+            (2, 3, [0.0, -5.0, 0.0]),
+        ];
+        assert_eq!(solve(4, &edges), vec![Some(1), Some(0), Some(3), Some(2)]);
+    }
+
+    #[test]
+    fn compares_weights_part_by_part() {
+        let square = |across: [f64; 3]| {
+            vec![
+                (0, 1, [1.0, 0.0, 0.0]),
+                (2, 3, [1.0, 0.0, 0.0]),
+                (0, 2, [1.0, 1.0, 0.0]),
+                (1, 3, across),
+            ]
+        };
+        // With equal first parts, the second decides.
+        let mates = solve(4, &square([1.0, -0.5, 0.0]));
+        assert_eq!(mates, vec![Some(2), Some(3), Some(0), Some(1)]);
+        // A smaller first part loses however large the second.
+        let mates = solve(4, &square([0.5, 100.0, 0.0]));
+        assert_eq!(mates, vec![Some(1), Some(0), Some(3), Some(2)]);
+    }
+
+    /// Checks that the edges `(i, j, weight)`, with only a first part to
+    /// their weights, give vertices `1..` the `mates` and leave vertex 0
+    /// unmatched.
+    fn check(edges: &[(usize, usize, f64)], mates: &[usize]) {
+        let edges = edges
+            .iter()
+            .map(|&(i, j, weight)| (i, j, [weight, 0.0, 0.0]))
+            .collect::<Vec<_>>();
+        let expected = [None]
+            .into_iter()
+            .chain(mates.iter().map(|x| Some(*x)))
+            .collect::<Vec<_>>();
+        assert_eq!(solve(mates.len() + 1, &edges), expected);
+    }
+
+    /// Graphs whose search builds blossoms inside blossoms, relabels them
+    /// and breaks them up again.
+    #[test]
+    fn expands_nested_blossoms() {
+        check(
+            &[
+                (1, 2, 9.0),
+                (1, 3, 9.0),
+                (2, 3, 10.0),
+                (2, 4, 8.0),
+                (3, 5, 8.0),
+                (4, 5, 10.0),
+                (5, 6, 6.0),
+            ],
+            &[3, 4, 1, 2, 6, 5],
+        );
+        check(
+            &[
+                (1, 2, 23.0),
+                (1, 5, 22.0),
+                (1, 6, 15.0),
+                (2, 3, 25.0),
+                (3, 4, 22.0),
+                (4, 5, 25.0),
+                (4, 8, 14.0),
+                (5, 7, 13.0),
+            ],
+            &[6, 3, 2, 8, 7, 1, 5, 4],
+        );
+        check(
+            &[
+                (1, 2, 19.0),
+                (1, 3, 20.0),
+                (1, 8, 8.0),
+                (2, 3, 25.0),
+                (2, 4, 18.0),
+                (3, 5, 18.0),
+                (4, 5, 13.0),
+                (4, 7, 7.0),
+                (5, 6, 7.0),
+            ],
+            &[8, 3, 2, 7, 6, 5, 4, 1],
+        );
+    }
+}
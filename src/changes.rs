@@ -0,0 +1,87 @@
+//! What changed between two records: pairs that formed or broke up, and
+//! who moved room. People are matched by [`Record::identity`], so a
+//! renamed person with an `id` isn't reported as a change.
+
+use std::collections::BTreeSet;
+
+use crate::history::Record;
+
+/// The changes from one record to the next, by the names in the newer one
+/// (or the older one, for someone who has since left).
+#[derive(Debug, Default)]
+pub struct Changes {
+    /// Roommates who didn't share last time, with their new room.
+    pub formed: Vec<(String, String, String)>,
+    /// Roommates from last time who no longer share.
+    pub broken: Vec<(String, String)>,
+    /// People in a different room than last time, from and to.
+    pub moved: Vec<(String, String, String)>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.formed.is_empty() && self.broken.is_empty() && self.moved.is_empty()
+    }
+}
+
+/// Every pair of roommates in `record`, by identity, each in order, with
+/// their room.
+fn pairs(record: &Record) -> BTreeSet<(&str, &str, &str)> {
+    let mut pairs = BTreeSet::new();
+    for room in &record.rooms {
+        for (i, a) in room.people.iter().enumerate() {
+            for b in &room.people[i + 1..] {
+                let (a, b) = (record.identity(a), record.identity(b));
+                pairs.insert((a.min(b), a.max(b), room.name.as_str()));
+            }
+        }
+    }
+    pairs
+}
+
+/// The changes from `previous` to `current`. Moves are only counted when
+/// `named_rooms` is set, as otherwise room labels just follow the order
+/// rooms are listed in.
+pub fn changes(previous: &Record, current: &Record, named_rooms: bool) -> Changes {
+    let name = |identity: &str| {
+        [current, previous]
+            .into_iter()
+            .flat_map(|record| {
+                record
+                    .rooms
+                    .iter()
+                    .flat_map(|x| &x.people)
+                    .map(move |x| (record, x))
+            })
+            .find(|(record, x)| record.identity(x) == identity)
+            .map_or(identity.to_string(), |(_, x)| x.clone())
+    };
+    let (before, after) = (pairs(previous), pairs(current));
+    let together = |set: &BTreeSet<(&str, &str, &str)>, a: &str, b: &str| {
+        set.iter().any(|x| x.0 == a && x.1 == b)
+    };
+    let mut result = Changes::default();
+    for (a, b, room) in &after {
+        if !together(&before, a, b) {
+            result.formed.push((name(a), name(b), room.to_string()));
+        }
+    }
+    for (a, b, _) in &before {
+        if !together(&after, a, b) {
+            result.broken.push((name(a), name(b)));
+        }
+    }
+    if named_rooms {
+        for room in &current.rooms {
+            for person in &room.people {
+                let identity = current.identity(person);
+                if let Some(old) = previous.room_of(identity).filter(|x| x.name != room.name) {
+                    result
+                        .moved
+                        .push((person.clone(), old.name.clone(), room.name.clone()));
+                }
+            }
+        }
+    }
+    result
+}
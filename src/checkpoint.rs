@@ -0,0 +1,65 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::solver::{Problem, Solution};
+
+/// Enough of the sampler's state to carry on where it left off. Only the
+/// solutions tied for best are kept, since the rest can never be chosen.
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub people: Vec<String>,
+    pub seed: u64,
+    pub samples_done: u64,
+    pub rng_word_pos: u128,
+    pub best: Vec<Solution>,
+}
+
+impl Checkpoint {
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Makes sure the checkpoint was written for the same set of people.
+    pub fn check(&self, problem: &Problem) -> Result<()> {
+        if self.people != problem.people {
+            bail!("checkpoint was written for a different config");
+        }
+        Ok(())
+    }
+}
+
+/// Writes checkpoints to a file, at most once per interval.
+pub struct Checkpointer {
+    pub seed: u64,
+    path: PathBuf,
+    interval: Duration,
+    last: Instant,
+}
+
+impl Checkpointer {
+    pub fn new(path: PathBuf, interval: Duration, seed: u64) -> Self {
+        Self {
+            seed,
+            path,
+            interval,
+            last: Instant::now(),
+        }
+    }
+
+    pub fn due(&self) -> bool {
+        self.last.elapsed() >= self.interval
+    }
+
+    /// Replaces the checkpoint file, going through a temporary file so an
+    /// interrupted write never leaves a truncated checkpoint behind.
+    pub fn write(&mut self, checkpoint: &Checkpoint) -> Result<()> {
+        let temp = self.path.with_extension("tmp");
+        fs::write(&temp, serde_json::to_string(checkpoint)?)?;
+        fs::rename(&temp, &self.path)?;
+        self.last = Instant::now();
+        Ok(())
+    }
+}
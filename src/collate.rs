@@ -0,0 +1,43 @@
+//! The order names are listed in wherever they are shown.
+//!
+//! Names are compared by their letters first, ignoring accents and case,
+//! so that "Émile" sorts with "Emile" rather than after "Zoe". Accents
+//! only break ties (unaccented first), then case, then the exact
+//! characters, so two different names never compare equal and the order
+//! is the same from run to run.
+
+use icu_normalizer::DecomposingNormalizerBorrowed;
+use icu_properties::props::{GeneralCategory, GeneralCategoryGroup};
+use icu_properties::CodePointMapData;
+use std::cmp::Ordering;
+
+/// The letters of a name, ignoring case and, unless `accents` is set,
+/// accents.
+fn letters(name: &str, accents: bool) -> String {
+    let categories = CodePointMapData::<GeneralCategory>::new();
+    DecomposingNormalizerBorrowed::new_nfd()
+        .normalize(name)
+        .chars()
+        .filter(|x| accents || !GeneralCategoryGroup::Mark.contains(categories.get(*x)))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn key(name: &str) -> (String, String, String, String) {
+    (
+        letters(name, false),
+        letters(name, true),
+        name.to_lowercase(),
+        name.to_string(),
+    )
+}
+
+/// Compares two names in the order they are listed in.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    key(a).cmp(&key(b))
+}
+
+/// Sorts names into the order they are listed in.
+pub fn sort<T: AsRef<str>>(names: &mut [T]) {
+    names.sort_by_cached_key(|x| key(x.as_ref()));
+}
@@ -0,0 +1,161 @@
+use anyhow::{bail, Result};
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+
+use crate::collate;
+use crate::rng::Streams;
+use crate::solver::{
+    best_indices, build_rooms, contract, seat_leftover, tier, within_cap, Pairs, Problem, Solution,
+    Tier,
+};
+
+fn find(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    let mut i = i;
+    while parents[i] != root {
+        let next = parents[i];
+        parents[i] = root;
+        i = next;
+    }
+    root
+}
+
+/// Splits everyone into clusters joined by mutual preferences, largest
+/// first. People nobody mutually prefers end up in clusters of one.
+///
+/// Preferred rooms can only ever be formed inside a cluster, so a cluster
+/// of odd size always leaves at least one of its members without a
+/// preferred roommate.
+pub fn preference_components(problem: &Problem) -> Vec<Vec<String>> {
+    let index = problem
+        .people
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (x.as_str(), i))
+        .collect::<HashMap<_, _>>();
+    let mut parents = (0..problem.people.len()).collect::<Vec<_>>();
+    for (i, person) in problem.people.iter().enumerate() {
+        for other in &problem.constraints[person].0 {
+            let Some(&j) = index.get(other.as_str()) else {
+                continue;
+            };
+            if tier(person, other, problem) == Tier::Preferred {
+                let (a, b) = (find(&mut parents, i), find(&mut parents, j));
+                parents[a] = b;
+            }
+        }
+    }
+
+    let mut components = HashMap::<usize, Vec<String>>::new();
+    for (i, person) in problem.people.iter().enumerate() {
+        components
+            .entry(find(&mut parents, i))
+            .or_default()
+            .push(person.clone());
+    }
+    let mut components = components.into_values().collect::<Vec<_>>();
+    // Components are disjoint, so their first members tell them apart.
+    components.sort_by(|a, b| {
+        b.len()
+            .cmp(&a.len())
+            .then_with(|| collate::compare(&a[0], &b[0]))
+    });
+    components
+}
+
+/// Samples rooms for just `people`, returning the best rooms found along
+/// with whoever they leave without a roommate. Fewer leftovers always win,
+/// then the usual ranking applies.
+fn best_rooms(
+    problem: &Problem,
+    people: &[String],
+    budget: u64,
+    streams: &mut Streams,
+    worker: u64,
+    deterministic: bool,
+) -> Result<(Pairs, Vec<String>)> {
+    if deterministic {
+        return build_rooms(problem, people, None);
+    }
+    let mut rng = streams.get("components", worker);
+    let mut candidates = vec![];
+    let mut leftovers = vec![];
+    for _ in 0..budget {
+        let (pairs, leftover) = build_rooms(problem, people, Some(&mut rng))?;
+        let candidate = Solution::from_rooms(pairs, problem);
+        if within_cap(&candidate, problem) {
+            candidates.push(candidate);
+            leftovers.push(leftover);
+        }
+    }
+    if let (true, Some(cap)) = (candidates.is_empty(), problem.settings.max_unpreferred) {
+        bail!(
+            "no rooms for {} with at most {cap} unpreferred rooms were found; try a larger solutions budget",
+            people.join(", ")
+        );
+    }
+    let fewest = leftovers.iter().map(Vec::len).min().unwrap_or(0);
+    let (mut candidates, mut leftovers): (Vec<_>, Vec<_>) = candidates
+        .into_iter()
+        .zip(leftovers)
+        .filter(|(_, leftover)| leftover.len() == fewest)
+        .unzip();
+    let Some(&index) = best_indices(&candidates, problem.settings.ranking).choose(&mut rng) else {
+        return Ok((vec![], people.to_vec()));
+    };
+    Ok((
+        candidates.swap_remove(index).result,
+        leftovers.swap_remove(index),
+    ))
+}
+
+/// Solves each preference cluster on its own (leaving out pinned pairs,
+/// see [`contract`]), then pairs up whoever is left over across clusters. Preferred rooms can only form inside a cluster, so
+/// little is lost, and each small cluster gets the whole sample budget to
+/// itself, which searches it far more thoroughly than sampling everyone at
+/// once. The leftovers get `leftover_budget` from `[solver.components]`
+/// if it is set.
+pub fn solve(
+    problem: &Problem,
+    budget: u64,
+    streams: &mut Streams,
+    deterministic: bool,
+) -> Result<Solution> {
+    let (mut result, _) = contract(problem);
+    let mut leftover = vec![];
+    let mut worker = 0;
+    for mut component in preference_components(problem) {
+        component.retain(|x| !problem.fixed.contains_key(x));
+        if component.len() < 2 {
+            leftover.extend(component);
+            continue;
+        }
+        let (pairs, rest) =
+            best_rooms(problem, &component, budget, streams, worker, deterministic)?;
+        result.extend(pairs);
+        leftover.extend(rest);
+        worker += 1;
+    }
+    leftover.sort();
+    let leftover_budget = problem
+        .tuning
+        .components
+        .as_ref()
+        .and_then(|x| x.leftover_budget)
+        .unwrap_or(budget);
+    let (pairs, rest) = best_rooms(
+        problem,
+        &leftover,
+        leftover_budget,
+        streams,
+        worker,
+        deterministic,
+    )?;
+    result.extend(pairs);
+    let mut rng = (!deterministic).then(|| streams.get("third", 0));
+    let third = seat_leftover(&result, &rest, problem, rng.as_mut())?;
+    Ok(Solution::with_third(result, third, problem))
+}
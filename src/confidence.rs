@@ -0,0 +1,137 @@
+//! How much a larger budget could still gain, estimated from the sampled
+//! solutions with extreme-value statistics.
+//!
+//! The samples are split into blocks and a Gumbel distribution is fitted
+//! by maximum likelihood to the best value of each block. Counters only
+//! move in whole steps, so each block's best is taken to lie anywhere
+//! within half a step of it: a value the sampler reaches in nearly every
+//! block then fits as a ceiling rather than as the start of a long tail.
+//! Running the same number of samples again adds as many blocks, so the
+//! chance that any of them beats the best found is `1 - G(best)^blocks`.
+
+use std::f64::consts::PI;
+
+use crate::config::Ranking;
+use crate::solver::{ranking_key, Ranked};
+
+/// Fewer blocks than this say too little about the tail to be worth
+/// reporting.
+const MIN_BLOCKS: usize = 10;
+
+/// The Euler–Mascheroni constant, the mean of the standard Gumbel
+/// distribution.
+const EULER_GAMMA: f64 = 0.577_215_664_901_532_9;
+
+/// The Gumbel distribution function.
+fn gumbel(x: f64, location: f64, scale: f64) -> f64 {
+    (-(-(x - location) / scale).exp()).exp()
+}
+
+/// The estimated chance that sampling as many solutions again would find
+/// one better than the best among `samples`, going by the first thing
+/// `ranking` compares. None if there are too few samples to tell.
+pub fn better_chance(samples: &[impl Ranked], ranking: Ranking) -> Option<f64> {
+    let values = samples
+        .iter()
+        .map(|x| ranking_key(x, ranking)[0])
+        .collect::<Vec<_>>();
+    let size = (values.len() as f64).sqrt() as usize;
+    let blocks = values.len() / size.max(1);
+    if blocks < MIN_BLOCKS {
+        return None;
+    }
+    let mut maxima = values
+        .chunks_exact(size)
+        .map(|x| x.iter().copied().fold(f64::MIN, f64::max))
+        .collect::<Vec<_>>();
+    maxima.sort_by(f64::total_cmp);
+    let mut levels = Vec::<(f64, usize)>::new();
+    for x in &maxima {
+        match levels.last_mut() {
+            Some((value, count)) if value == x => *count += 1,
+            _ => levels.push((*x, 1)),
+        }
+    }
+    // Every block reached the same value, so nothing suggests there is
+    // anything above it.
+    if levels.len() == 1 {
+        return Some(0.0);
+    }
+    let half_step = levels
+        .windows(2)
+        .map(|x| x[1].0 - x[0].0)
+        .fold(f64::MAX, f64::min)
+        / 2.0;
+    let best = levels[levels.len() - 1].0;
+
+    let log_likelihood = |[location, log_scale]: [f64; 2]| {
+        let scale = log_scale.exp();
+        levels
+            .iter()
+            .map(|(x, count)| {
+                let p =
+                    gumbel(x + half_step, location, scale) - gumbel(x - half_step, location, scale);
+                *count as f64 * p.max(f64::MIN_POSITIVE).ln()
+            })
+            .sum::<f64>()
+    };
+    let mean = maxima.iter().sum::<f64>() / maxima.len() as f64;
+    let variance =
+        maxima.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (maxima.len() - 1) as f64;
+    let scale = (variance.sqrt() * 6f64.sqrt() / PI).max(half_step);
+    let [location, log_scale] = maximize(
+        log_likelihood,
+        [mean - EULER_GAMMA * scale, scale.ln()],
+        [scale, 1.0],
+    );
+
+    let exceed = 1.0 - gumbel(best + half_step, location, log_scale.exp());
+    Some(1.0 - (1.0 - exceed).powf(blocks as f64))
+}
+
+/// Finds a maximum of `f` near `start` with the Nelder–Mead method, taking
+/// first steps of `step` along each axis.
+fn maximize(f: impl Fn([f64; 2]) -> f64, start: [f64; 2], step: [f64; 2]) -> [f64; 2] {
+    let mut simplex = [
+        start,
+        [start[0] + step[0], start[1]],
+        [start[0], start[1] + step[1]],
+    ]
+    .map(|x| (f(x), x));
+    for _ in 0..500 {
+        simplex.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let [(best, _), (second, _), (worst, worst_at)] = simplex;
+        if best - worst < 1e-9 {
+            break;
+        }
+        let towards = |t: f64| {
+            let centre = [0, 1].map(|i| (simplex[0].1[i] + simplex[1].1[i]) / 2.0);
+            let x = [0, 1].map(|i| centre[i] + t * (worst_at[i] - centre[i]));
+            (f(x), x)
+        };
+        let reflected = towards(-1.0);
+        simplex[2] = if reflected.0 > best {
+            let expanded = towards(-2.0);
+            if expanded.0 > reflected.0 {
+                expanded
+            } else {
+                reflected
+            }
+        } else if reflected.0 > second {
+            reflected
+        } else {
+            let contracted = towards(0.5);
+            if contracted.0 > worst {
+                contracted
+            } else {
+                let top = simplex[0].1;
+                for point in &mut simplex[1..] {
+                    let x = [0, 1].map(|i| (top[i] + point.1[i]) / 2.0);
+                    *point = (f(x), x);
+                }
+                continue;
+            }
+        };
+    }
+    simplex.iter().max_by(|a, b| a.0.total_cmp(&b.0)).unwrap().1
+}
@@ -0,0 +1,927 @@
+use anyhow::{anyhow, bail, Result};
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::mem;
+use std::path::PathBuf;
+use toml::value::Datetime;
+use toml_edit::{Document, Table, TableLike};
+
+use crate::storage::Storage;
+use crate::{date, migrate, snapshot};
+
+/// A room-matcher config file.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub config: Settings,
+    /// Everyone taking part, keyed by name.
+    #[serde(default)]
+    pub people: BTreeMap<String, Person>,
+    /// The rooms available, keyed by name, in the order they should be
+    /// filled. Without this section rooms are simply numbered.
+    #[serde(default)]
+    pub rooms: IndexMap<String, Room>,
+    /// Separate places people stay at, e.g. two hotels, each with its own
+    /// rooms, keyed by name. Their rooms are filled along with any under
+    /// `[rooms]`.
+    #[serde(default)]
+    pub venues: IndexMap<String, Venue>,
+    /// Conditions relating people's rooms to each other, see `rules.rs`.
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// Pairs decided ahead of time, which share a room whatever their
+    /// preferences while everyone else is placed around them.
+    #[serde(default)]
+    pub pins: Vec<Pin>,
+    /// Parameters for the solver strategies, one section per strategy.
+    #[serde(default)]
+    pub solver: Tuning,
+    /// A cap on how unevenly preferred rooms may fall across the values of
+    /// one attribute.
+    pub fairness: Option<Fairness>,
+}
+
+/// `[[pins]]`: two people who must share a room, e.g.
+/// `pair = ["Ana", "Bea"]`. Works as `partner_of` does, without either
+/// person's entry having to say so.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Pin {
+    pub pair: [String; 2],
+}
+
+/// `[fairness]`: a cap on how unevenly preferred rooms are spread, and a
+/// boost for newcomers.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Fairness {
+    /// A key of people's `attributes`, or `house` or `role`. Solutions
+    /// where the share of people with a preferred room differs by more
+    /// than `max_gap` between any two of its values (e.g. 0.2 for 20
+    /// percentage points) are thrown away. Give both or neither.
+    pub attribute: Option<String>,
+    pub max_gap: Option<f64>,
+    /// Soft-score bonus for each newcomer sharing with someone they
+    /// listed as preferred, and newcomers pick their roommates before
+    /// anyone else who isn't pinned. Newcomers are people in fewer than
+    /// `newcomer_runs` runs of the `--history` file.
+    #[serde(default)]
+    pub newcomer_boost: f64,
+    #[serde(default = "default_newcomer_runs")]
+    pub newcomer_runs: usize,
+}
+
+fn default_newcomer_runs() -> usize {
+    3
+}
+
+impl Fairness {
+    /// The attribute and `max_gap` of the cap, if there is one.
+    pub fn cap(&self) -> Option<(&str, f64)> {
+        Some((self.attribute.as_deref()?, self.max_gap?))
+    }
+}
+
+/// A rule such as `not adjacent(Ana, Bea)`. Rules are soft: each one that
+/// doesn't hold takes its weight off the soft score.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub rule: String,
+    #[serde(default = "default_rule_weight")]
+    pub weight: f64,
+    /// Rules with a higher priority are never broken to keep rules with a
+    /// lower one, whatever their weights.
+    #[serde(default)]
+    pub priority: i64,
+}
+
+fn default_rule_weight() -> f64 {
+    1.0
+}
+
+/// Parameters for tuning the solver strategies, e.g. `[solver.sample]`.
+/// Only the section of the strategy in use may be given.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Tuning {
+    pub sample: Option<SampleTuning>,
+    pub components: Option<ComponentsTuning>,
+}
+
+/// Parameters for `solver = "sample"`.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SampleTuning {
+    /// Stop early once this many solutions in a row haven't beaten the
+    /// best so far, rather than always generating `solutions`.
+    pub patience: Option<u64>,
+    /// Abandon a solution while it's being generated once it has more
+    /// unpreferred rooms than it could be kept with. Off by default, as it
+    /// changes which solutions a seed gives.
+    #[serde(default)]
+    pub prune: bool,
+}
+
+/// Parameters for `solver = "components"`.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ComponentsTuning {
+    /// How many solutions to sample when pairing up the people left over
+    /// across clusters; by default as many as for each cluster.
+    pub leftover_budget: Option<u64>,
+}
+
+impl Tuning {
+    /// Fails if a section is given for a strategy other than `strategy`,
+    /// or at all in bipartite mode, or a parameter is out of range.
+    pub fn check(&self, mode: Mode, strategy: Strategy) -> Result<()> {
+        let given = [
+            (self.sample.is_some(), Strategy::Sample, "sample"),
+            (
+                self.components.is_some(),
+                Strategy::Components,
+                "components",
+            ),
+        ];
+        for (given, section, name) in given {
+            if !given {
+                continue;
+            }
+            if mode == Mode::Bipartite {
+                bail!("[solver.{name}] is set, but bipartite mode is solved exactly without a strategy");
+            }
+            if section != strategy {
+                bail!("[solver.{name}] is set, but the solver in use is not \"{name}\"");
+            }
+        }
+        if self.sample.as_ref().and_then(|x| x.patience) == Some(0) {
+            bail!("[solver.sample] patience must be at least 1");
+        }
+        if self.components.as_ref().and_then(|x| x.leftover_budget) == Some(0) {
+            bail!("[solver.components] leftover_budget must be at least 1");
+        }
+        Ok(())
+    }
+}
+
+/// A named room.
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Room {
+    /// Tags this room suits, e.g. "quiet" for a room away from the stairs.
+    #[serde(default)]
+    pub themes: Vec<String>,
+    /// How many people this room holds, if not `room_size`.
+    pub size: Option<usize>,
+}
+
+/// A place people stay at, under `[venues]`.
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Venue {
+    /// The venue's rooms, keyed by name, in the order they should be
+    /// filled. Names must be unique across every venue and `[rooms]`.
+    #[serde(default)]
+    pub rooms: IndexMap<String, Room>,
+}
+
+/// General settings for a run.
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    /// How many random solutions to generate before picking the best one.
+    pub solutions: u64,
+    /// The version of the config format this file was written for.
+    pub schema_version: i64,
+    /// Bonus for keeping a pair from the current rooms together, given as
+    /// a tie-break between solutions with equal counters.
+    #[serde(default)]
+    pub keep_current_weight: f64,
+    /// How rooms are formed.
+    #[serde(default)]
+    pub mode: Mode,
+    /// How many people each room holds. Rooms of more than two are filled
+    /// by sampling, see `sizes.rs`, which leaves out a few options that
+    /// only make sense for pairs.
+    #[serde(default = "default_room_size")]
+    pub room_size: usize,
+    /// How solutions are searched for in pairs mode.
+    #[serde(default)]
+    pub solver: Strategy,
+    /// Warn about people whose `submitted` date is older than this many days.
+    pub max_preference_age_days: Option<u64>,
+    /// Soft-score penalty for each preferred room that relies on the
+    /// preferences of someone past `max_preference_age_days`.
+    #[serde(default)]
+    pub stale_penalty: f64,
+    /// Soft-score penalty for each room where neither person listed the
+    /// other as preferred, so rooms of strangers are avoided when possible.
+    #[serde(default)]
+    pub no_link_penalty: f64,
+    /// The order solutions are ranked in.
+    #[serde(default)]
+    pub ranking: Ranking,
+    /// Warn about anyone who can share with fewer than this many people
+    /// without an unpreferred room.
+    #[serde(default = "default_min_feasible_partners")]
+    pub min_feasible_partners: usize,
+    /// Whether people should move between named rooms from one run in the
+    /// `--history` file to the next.
+    #[serde(default)]
+    pub room_reuse: RoomReuse,
+    /// How many rooms from `[rooms]` to leave empty for late arrivals.
+    /// Which ones is decided along with everyone else's rooms.
+    #[serde(default)]
+    pub reserve_rooms: usize,
+    /// The most unpreferred rooms a solution may have. Solutions with more
+    /// are thrown away, however well they do otherwise.
+    pub max_unpreferred: Option<usize>,
+    /// The most entries anyone's `preferred` list counts, for people who
+    /// paste in their whole class. Longer lists are warned about and cut
+    /// down as `long_lists` says.
+    pub max_preferred: Option<usize>,
+    #[serde(default)]
+    pub long_lists: LongLists,
+    /// Only let people share a room with others of the same `role`, anyone
+    /// without one counting as a role of their own, e.g. to keep staff and
+    /// students apart.
+    #[serde(default)]
+    pub separate_roles: bool,
+    /// How rooms are labelled without `[rooms]`, e.g. "Cabin {letter}" or
+    /// "Dorm {number:02}". Defaults to "ROOM {number}".
+    pub room_names: Option<String>,
+    /// Soft-score penalty for each person who listed people as preferred
+    /// but shares with none of them, so solutions that leave the same
+    /// number of preferred rooms spread that disappointment thinner.
+    #[serde(default)]
+    pub disappointment_penalty: f64,
+    /// Soft-score bonus for each tag two roommates share.
+    #[serde(default)]
+    pub tag_weight: f64,
+    /// Soft-score bonus for each tag of a person that is one of their
+    /// room's themes.
+    #[serde(default)]
+    pub theme_weight: f64,
+    /// Soft-score bonus for each unit of `weights` two roommates give
+    /// each other.
+    #[serde(default)]
+    pub rating_weight: f64,
+    /// Soft-score penalty for putting two popular people together: each
+    /// room loses this times the product of its people's popularity, from
+    /// 0 to 1 by how many others listed them as preferred, so well-liked
+    /// people are spread across rooms rather than sharing with each other.
+    #[serde(default)]
+    pub popularity_penalty: f64,
+    /// Soft-score penalty for each place someone's room is from the size
+    /// they'd like by their `size_pref`, and for anyone left alone in a
+    /// room unless their `size_pref` is "single_ok".
+    #[serde(default)]
+    pub size_pref_penalty: f64,
+}
+
+fn default_min_feasible_partners() -> usize {
+    3
+}
+
+fn default_room_size() -> usize {
+    2
+}
+
+/// The order counters are compared in when ranking solutions. Each later
+/// counter only breaks ties in the earlier ones.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Ranking {
+    /// Most preferred rooms, then most accepted rooms, then the soft score.
+    #[default]
+    Preferred,
+    /// Fewest unpreferred rooms, then most preferred rooms, then the soft
+    /// score.
+    Avoids,
+    /// Highest soft score, then most preferred rooms, then most accepted
+    /// rooms.
+    Score,
+}
+
+/// A named set of ranking and soft weights, so the objective can be chosen
+/// without tuning weights by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    /// Keep unpreferred rooms to an absolute minimum, then avoid rooms of
+    /// strangers.
+    StrictAvoids,
+    /// As many mutually preferred rooms as possible, favouring fresh
+    /// preferences.
+    MaximizeFirstChoices,
+    /// As few rooms of strangers as possible, so everyone shares with
+    /// someone at least one of them asked for.
+    FairnessFirst,
+    /// Keep as many current rooms (from `--current`) together as possible.
+    StabilityFirst,
+}
+
+impl Preset {
+    /// Replaces the ranking and soft weights in `settings` with this
+    /// preset's.
+    pub fn apply(self, settings: &mut Settings) {
+        let (ranking, keep_current_weight, stale_penalty, no_link_penalty) = match self {
+            Self::StrictAvoids => (Ranking::Avoids, 0.0, 0.0, 1.0),
+            Self::MaximizeFirstChoices => (Ranking::Preferred, 0.0, 1.0, 0.0),
+            Self::FairnessFirst => (Ranking::Score, 0.0, 0.0, 1.0),
+            Self::StabilityFirst => (Ranking::Score, 1.0, 0.0, 0.0),
+        };
+        settings.ranking = ranking;
+        settings.keep_current_weight = keep_current_weight;
+        settings.stale_penalty = stale_penalty;
+        settings.no_link_penalty = no_link_penalty;
+    }
+}
+
+/// How large a room someone would like to be in, for `size_pref_penalty`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SizePref {
+    /// As few roommates as possible: each one past the first counts
+    /// against the room.
+    Small,
+    /// As many roommates as possible: each place their room holds fewer
+    /// than the largest room counts against it.
+    Large,
+    /// Happy to have a room to themselves.
+    SingleOk,
+}
+
+/// What happens to the entries of a `preferred` list past `max_preferred`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum LongLists {
+    /// They're dropped.
+    #[default]
+    Truncate,
+    /// They become `weights` of `max_preferred` over the length of the
+    /// list, so the longer the list the less each counts, towards the soft
+    /// score with `rating_weight`. Weights the person gave are kept.
+    DownWeight,
+}
+
+/// How named rooms are handed out across the runs in a history file, e.g.
+/// the nights of a trip.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RoomReuse {
+    /// Rooms are filled in order regardless of history.
+    #[default]
+    Ignore,
+    /// Nobody is put in a room they've been in before, where possible.
+    Avoid,
+    /// Everyone stays in their most recent room, where possible.
+    Keep,
+}
+
+/// How solutions are searched for in pairs mode.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Strategy {
+    /// Sample `solutions` random greedy solutions over everyone and keep
+    /// the best.
+    #[default]
+    Sample,
+    /// Sample each cluster of mutual preferences separately with the full
+    /// budget, then pair up whoever is left over. Much stronger on large
+    /// configs made of many small friendship groups.
+    Components,
+    /// Find the very best way of pairing people up as a maximum-weight
+    /// matching, as with `--fast`. Only for up to 400 people, without
+    /// rules, room themes or `max_unpreferred`.
+    Exact,
+    /// Pick one of the others from the number of people and how their
+    /// preferences are spread, and say which.
+    Auto,
+}
+
+/// How rooms are formed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Anyone may share a room with anyone else.
+    #[default]
+    Pairs,
+    /// Everyone has a `group`, and every room holds one person from each of
+    /// the two groups (e.g. mentors and mentees). Solved exactly.
+    Bipartite,
+}
+
+/// The preferences of a single person.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Person {
+    /// People this person would like to share a room with.
+    #[serde(default)]
+    pub preferred: Vec<Entry>,
+    /// People this person would rather not share a room with.
+    #[serde(default)]
+    pub unpreferred: Vec<Entry>,
+    /// People this person must never share a room with, e.g. for
+    /// safeguarding. Unlike `unpreferred`, this is never given up on:
+    /// a run fails rather than put them together.
+    #[serde(default)]
+    pub forbidden: Vec<String>,
+    /// Which of the two groups this person belongs to in bipartite mode.
+    pub group: Option<String>,
+    /// When this person's preferences were collected, e.g. 2024-09-01.
+    #[schemars(with = "Option<String>")]
+    pub submitted: Option<Datetime>,
+    /// The name of a room from `[rooms]` (or a venue's rooms) this person
+    /// must be placed in.
+    pub room: Option<String>,
+    /// The venue from `[venues]` this person must stay at. Being pinned to
+    /// one of its rooms has the same effect.
+    pub venue: Option<String>,
+    /// The venue from `[venues]` this person would rather stay at, which
+    /// their room is put in where the rooms allow.
+    pub preferred_venue: Option<String>,
+    /// Interests or habits, e.g. "quiet" or "early-riser", matched against
+    /// roommates' tags and room themes.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The most people this person is willing to share a room with,
+    /// themselves included. Rooms hold 2, apart from one room of 3 when
+    /// there's an odd number of people, unless `room_size` or a room's
+    /// `size` says otherwise.
+    pub max_room_size: Option<usize>,
+    /// How large a room this person would like: "small", "large" or
+    /// "single_ok". Counts towards the soft score with `size_pref_penalty`.
+    pub size_pref: Option<SizePref>,
+    /// Whether this person may be one of three in a room. With an odd
+    /// number of people, one room holds three, all of whom must allow it.
+    #[serde(default)]
+    pub allow_triple: bool,
+    /// Whether this person didn't submit preferences and will go wherever
+    /// they're needed: they're placed last, to even out the numbers and
+    /// fill the rooms nobody else fits.
+    #[serde(default)]
+    pub flexible: bool,
+    /// How much this person would like to share with each of these
+    /// people, from -1 (rather not) to 1 (very much), e.g. from a ratings
+    /// survey. Counts towards the soft score with `rating_weight`.
+    #[serde(default)]
+    pub weights: BTreeMap<String, f64>,
+    /// A stable identifier, e.g. a student number, that follows this
+    /// person through the history even if their name changes. Without
+    /// one, they are known by their name.
+    pub id: Option<String>,
+    /// The house, form or other unit this person belongs to, for the
+    /// rooms grouped by house with `--by-house`.
+    pub house: Option<String>,
+    /// Whether this person has a medical need duty staff should know
+    /// about, which flags their room in every list of rooms.
+    #[serde(default)]
+    pub medical: bool,
+    /// Anything duty staff should know, e.g. "nut allergy", shown with
+    /// their room in every list of rooms.
+    pub notes: Option<String>,
+    /// Someone this person must share a room with, e.g. their partner on
+    /// a staff trip. Naming them on one side is enough.
+    pub partner_of: Option<String>,
+    /// What this person is on the trip, e.g. "staff" or "student". With
+    /// `separate_roles`, people only share with others of the same role.
+    pub role: Option<String>,
+    /// Anything else about this person to audit the rooms by with
+    /// `--audit`, e.g. `{ gender = "f", year = "10" }`.
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// Someone listed in `preferred` or `unpreferred`, either just by name or
+/// with the dates the entry applies between, e.g.
+/// `{ name = "Bea", until = 2024-10-01 }`.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Entry {
+    Name(String),
+    Windowed(WindowedEntry),
+}
+
+/// An entry that only applies between two dates (both inclusive).
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WindowedEntry {
+    pub name: String,
+    /// The first date the entry applies on.
+    #[schemars(with = "Option<String>")]
+    pub from: Option<Datetime>,
+    /// The last date the entry applies on.
+    #[schemars(with = "Option<String>")]
+    pub until: Option<Datetime>,
+}
+
+impl Entry {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Name(x) => x,
+            Self::Windowed(x) => &x.name,
+        }
+    }
+
+    /// Whether the entry applies on `day` (days since 1970-01-01).
+    pub fn applies_on(&self, day: i64) -> Result<bool> {
+        let Self::Windowed(entry) = self else {
+            return Ok(true);
+        };
+        if let Some(from) = &entry.from {
+            if day < date::from_toml(from)? {
+                return Ok(false);
+            }
+        }
+        if let Some(until) = &entry.until {
+            if day > date::from_toml(until)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl Config {
+    /// Moves the rooms of every venue into `rooms`, after any listed there,
+    /// and returns the venue each of them is at.
+    pub fn take_venue_rooms(&mut self) -> Result<IndexMap<String, String>> {
+        let mut venues = IndexMap::new();
+        for (venue, listed) in &mut self.venues {
+            for (name, room) in mem::take(&mut listed.rooms) {
+                if self.rooms.contains_key(&name) {
+                    bail!("\"{name}\" is listed twice across [rooms] and [venues]; give the rooms of each venue their own names, e.g. \"{venue} {name}\"");
+                }
+                self.rooms.insert(name.clone(), room);
+                venues.insert(name, venue.clone());
+            }
+        }
+        Ok(venues)
+    }
+
+    /// The venue `person` must stay at, from their `venue` or the room
+    /// they're pinned to, given the venue of each room.
+    pub fn venue_of<'a>(
+        person: &'a Person,
+        room_venues: &'a IndexMap<String, String>,
+    ) -> Option<&'a String> {
+        person
+            .venue
+            .as_ref()
+            .or_else(|| room_venues.get(person.room.as_ref()?))
+    }
+
+    /// Makes sure every venue named by someone is listed under
+    /// `[venues]`, that nobody has to be at two venues at once, and that
+    /// each venue has rooms for everyone who must stay there, given the
+    /// venue of each room (see [`Config::take_venue_rooms`]).
+    pub fn check_venues(&self, room_venues: &IndexMap<String, String>) -> Result<()> {
+        let mut staying = IndexMap::<&str, usize>::new();
+        for (name, person) in &self.people {
+            for (field, venue) in [
+                ("venue", &person.venue),
+                ("preferred_venue", &person.preferred_venue),
+            ] {
+                if let Some(venue) = venue.as_ref().filter(|x| !self.venues.contains_key(*x)) {
+                    bail!("{name} has {field} = \"{venue}\", which is not listed under [venues]");
+                }
+            }
+            if let Some((venue, room)) = person.venue.as_ref().zip(person.room.as_ref()) {
+                if let Some(other) = room_venues.get(room).filter(|x| *x != venue) {
+                    bail!("{name} must stay at {venue}, but is pinned to \"{room}\" at {other}");
+                }
+            }
+            let venue = Self::venue_of(person, room_venues);
+            if let Some(partner) = &person.partner_of {
+                let other = self
+                    .people
+                    .get(partner)
+                    .and_then(|x| Self::venue_of(x, room_venues));
+                if let Some((a, b)) = venue.zip(other).filter(|(a, b)| a != b) {
+                    bail!("{name} and their partner {partner} must stay at different venues, {a} and {b}");
+                }
+            }
+            if let Some(venue) = venue {
+                *staying.entry(venue).or_default() += 1;
+            }
+        }
+        for Pin { pair: [a, b] } in &self.pins {
+            let venue = |x| {
+                self.people
+                    .get(x)
+                    .and_then(|x| Self::venue_of(x, room_venues))
+            };
+            if let Some((x, y)) = venue(a).zip(venue(b)).filter(|(x, y)| x != y) {
+                bail!("{a} and {b} are pinned as a pair, but must stay at different venues, {x} and {y}");
+            }
+        }
+        for (venue, count) in staying {
+            let rooms = room_venues.values().filter(|x| *x == venue).count();
+            if rooms < count.div_ceil(2) {
+                bail!(
+                    "{count} people must stay at {venue}, which needs {} rooms, but only {rooms} are listed under [venues.\"{venue}\".rooms]",
+                    count.div_ceil(2)
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether any room holds more than two people, see `sizes.rs`.
+    pub fn sized(&self) -> bool {
+        self.config.room_size > 2 || self.rooms.values().any(|x| x.size.is_some_and(|x| x > 2))
+    }
+
+    /// Makes sure room pins refer to real rooms, pinned pairs to real
+    /// people, and that they can all be honoured at once, that everyone
+    /// fits in a room of the size they accept, and that no two people share
+    /// an `id`.
+    pub fn check_rooms(&self) -> Result<()> {
+        let size = self.config.room_size;
+        if size < 2 {
+            bail!("room_size = {size}, but rooms hold at least 2 people");
+        }
+        for (name, room) in &self.rooms {
+            if let Some(size) = room.size.filter(|x| *x < 2) {
+                bail!("\"{name}\" has size = {size}, but rooms hold at least 2 people");
+            }
+        }
+        let mut pinned = IndexMap::<&str, Vec<&str>>::new();
+        let mut ids = BTreeMap::<&str, &str>::new();
+        let mut partners = BTreeMap::<&str, &str>::new();
+        for (name, person) in &self.people {
+            if let Some(id) = &person.id {
+                if let Some(other) = ids.insert(id, name) {
+                    bail!("{other} and {name} both have id = \"{id}\"");
+                }
+            }
+            if let Some(size) = person.max_room_size.filter(|x| *x < 2) {
+                bail!("{name} has max_room_size = {size}, but rooms hold at least 2 people");
+            }
+            if let Some(size) = person
+                .max_room_size
+                .filter(|x| *x < 3 && person.allow_triple)
+            {
+                bail!("{name} has allow_triple = true, but max_room_size = {size}");
+            }
+            for other in &person.forbidden {
+                if other == name {
+                    bail!("{name} lists themselves as forbidden");
+                }
+                let Some(them) = self.people.get(other) else {
+                    bail!("{name} has {other} as forbidden, who isn't anyone in the config");
+                };
+                if person.partner_of.as_ref() == Some(other)
+                    || them.partner_of.as_ref() == Some(name)
+                {
+                    bail!("{name} and {other} are partners, but {name} has {other} as forbidden");
+                }
+            }
+            if let Some(partner) = &person.partner_of {
+                let Some(other) = self.people.get(partner).filter(|_| partner != name) else {
+                    bail!("{name} has partner_of = \"{partner}\", who isn't anyone else in the config");
+                };
+                for (a, b) in [(name, partner), (partner, name)] {
+                    if let Some(other) = partners.insert(a, b).filter(|x| x != b) {
+                        bail!("{a} is partnered with both {other} and {b}");
+                    }
+                }
+                if let Some((a, b)) = person
+                    .room
+                    .as_ref()
+                    .zip(other.room.as_ref())
+                    .filter(|(a, b)| a != b)
+                {
+                    bail!("{name} and their partner {partner} are pinned to different rooms, \"{a}\" and \"{b}\"");
+                }
+                if self.config.separate_roles && person.role != other.role {
+                    bail!("{name} and their partner {partner} have different roles, but separate_roles is set");
+                }
+            }
+            if let Some(room) = &person.room {
+                if !self.rooms.contains_key(room) {
+                    bail!("{name} is pinned to \"{room}\", which is not listed under [rooms]");
+                }
+                pinned.entry(room).or_default().push(name);
+            }
+        }
+        for Pin { pair: [a, b] } in &self.pins {
+            if a == b {
+                bail!("[[pins]] pairs {a} with themselves");
+            }
+            let (Some(x), Some(y)) = (self.people.get(a), self.people.get(b)) else {
+                let unknown = if self.people.contains_key(a) { b } else { a };
+                bail!("[[pins]] pairs {a} with {b}, but {unknown} isn't anyone in the config");
+            };
+            for (p, q) in [(a, b), (b, a)] {
+                if let Some(other) = partners.insert(p, q).filter(|x| x != q) {
+                    bail!("{p} is pinned as a pair with {q}, but must already share with {other}");
+                }
+            }
+            if x.forbidden.contains(b) || y.forbidden.contains(a) {
+                bail!("{a} and {b} are pinned as a pair, but one has the other as forbidden");
+            }
+            if let Some((x, y)) = x.room.as_ref().zip(y.room.as_ref()).filter(|(x, y)| x != y) {
+                bail!(
+                    "{a} and {b} are pinned as a pair, but to different rooms, \"{x}\" and \"{y}\""
+                );
+            }
+            if self.config.separate_roles && x.role != y.role {
+                bail!("{a} and {b} are pinned as a pair, but have different roles and separate_roles is set");
+            }
+        }
+        for (room, people) in &pinned {
+            let fits = self.rooms[*room].size.unwrap_or(size);
+            if people.len() > fits {
+                bail!(
+                    "\"{room}\" only fits {fits} people, but {} are pinned to it",
+                    people.join(", ")
+                );
+            }
+            for a in people {
+                if let Some(b) = self.people[*a]
+                    .forbidden
+                    .iter()
+                    .find(|x| people.contains(&x.as_str()))
+                {
+                    bail!(
+                        "{a} and {b} are both pinned to \"{room}\", but {a} has {b} as forbidden"
+                    );
+                }
+            }
+            // In a larger room, people pinned together can each share
+            // with their partner too.
+            if fits > 2 {
+                continue;
+            }
+            if let [a, b] = people[..] {
+                if let Some(partner) = partners.get(a).filter(|x| **x != b) {
+                    bail!(
+                        "{a} and {b} are pinned to \"{room}\", but {a} is partnered with {partner}"
+                    );
+                }
+                if let Some(partner) = partners.get(b).filter(|x| **x != a) {
+                    bail!(
+                        "{a} and {b} are pinned to \"{room}\", but {b} is partnered with {partner}"
+                    );
+                }
+            }
+        }
+        if let Some(fairness) = &self.fairness {
+            if fairness.attribute.is_some() != fairness.max_gap.is_some() {
+                bail!("[fairness] needs both attribute and max_gap to cap the gap, or neither");
+            }
+            if let Some(gap) = fairness.max_gap.filter(|x| !(0.0..=1.0).contains(x)) {
+                bail!("[fairness] max_gap = {gap} is not between 0 and 1");
+            }
+        }
+        if self.config.max_preferred == Some(0) {
+            bail!("max_preferred must be at least 1");
+        }
+        let reserve = self.config.reserve_rooms;
+        if reserve > 0 && self.rooms.is_empty() {
+            bail!("reserve_rooms needs the available rooms listed under [rooms]");
+        }
+        if self.sized() {
+            let space = self
+                .rooms
+                .values()
+                .map(|x| x.size.unwrap_or(size))
+                .sum::<usize>();
+            if !self.rooms.is_empty() && space < self.people.len() {
+                bail!(
+                    "{} people need more room than the {space} places in the rooms listed under [rooms]",
+                    self.people.len()
+                );
+            }
+            return Ok(());
+        }
+        // A room of three saves a room when there's an odd number of people.
+        let triples = self.people.values().any(|x| x.allow_triple);
+        let needed = if triples && self.people.len() >= 3 {
+            self.people.len() / 2
+        } else {
+            self.people.len().div_ceil(2)
+        } + reserve;
+        if !self.rooms.is_empty() && self.rooms.len() < needed {
+            bail!(
+                "{} people{} need {needed} rooms, but only {} are listed under [rooms]",
+                self.people.len(),
+                if reserve > 0 {
+                    format!(" and {reserve} reserved")
+                } else {
+                    String::new()
+                },
+                self.rooms.len()
+            );
+        }
+        let alone = pinned.values().filter(|x| x.len() == 1).count();
+        let unpinned = self.people.values().filter(|x| x.room.is_none()).count();
+        if alone > unpinned {
+            bail!(
+                "{alone} people are pinned to rooms on their own, but only {unpinned} people are left to share with them"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Merges `other` into `base`: tables are merged key by key, and any other
+/// value in `other` replaces the one in `base`.
+fn merge(base: &mut dyn TableLike, other: Table) {
+    for (key, item) in other {
+        let item = match base.get_mut(&key) {
+            Some(existing) if existing.is_table_like() && item.is_table_like() => {
+                match item.into_table() {
+                    Ok(table) => {
+                        if let Some(existing) = existing.as_table_like_mut() {
+                            merge(existing, table);
+                        }
+                        continue;
+                    }
+                    Err(item) => item,
+                }
+            }
+            _ => item,
+        };
+        base.insert(&key, item);
+    }
+}
+
+/// The highest position of any table in `table`, i.e. of the last table
+/// header in the file.
+fn last_position(table: &Table) -> usize {
+    table
+        .iter()
+        .filter_map(|(_, item)| item.as_table())
+        .map(|x| x.position().unwrap_or(0).max(last_position(x)))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Moves every table in `table` down by `offset` positions, so tables
+/// merged in from a later file are printed after the ones already there.
+fn shift_positions(table: &mut Table, offset: usize) {
+    for (_, item) in table.iter_mut() {
+        if let Some(table) = item.as_table_mut() {
+            if let Some(position) = table.position() {
+                table.set_position(position + offset);
+            }
+            shift_positions(table, offset);
+        }
+    }
+}
+
+/// Reads config files and merges them in order, each upgraded from older
+/// schema versions first: later files add people and rooms and override
+/// settings and fields of people already given. Files after the first that
+/// don't declare a schema version are taken to be written in the same one
+/// as the first. A snapshot written by `freeze` must be given on its own,
+/// and is checked against its hash.
+///
+/// Returns the merged config along with the oldest schema version among
+/// the files.
+pub fn read_merged(storage: &Storage, paths: &[PathBuf]) -> Result<(Document, i64)> {
+    let mut merged: Option<(Document, i64)> = None;
+    for path in paths {
+        let text = storage.read(path)?;
+        if snapshot::is_snapshot(&text) {
+            snapshot::verify(&text, path)?;
+            if paths.len() > 1 {
+                bail!(
+                    "{} is a frozen snapshot and can't be merged with other config files",
+                    path.display()
+                );
+            }
+        }
+        let mut doc = text
+            .parse::<Document>()
+            .map_err(|e| anyhow!("{}: {e}", path.display()))?;
+        match &mut merged {
+            None => {
+                let version = migrate::upgrade(&mut doc)?;
+                merged = Some((doc, version));
+            }
+            Some((base, oldest)) => {
+                let default = migrate::schema_version(base)?.unwrap_or(*oldest);
+                let version = migrate::upgrade_from(&mut doc, default)?;
+                *oldest = version.min(*oldest);
+                shift_positions(doc.as_table_mut(), last_position(base) + 1);
+                merge(base.as_table_mut(), mem::take(doc.as_table_mut()));
+            }
+        }
+    }
+    merged.ok_or_else(|| anyhow!("no config files given"))
+}
+
+/// Parses an already upgraded config.
+pub fn parse(doc: &Document) -> Result<Config> {
+    Ok(toml::from_str(&doc.to_string())?)
+}
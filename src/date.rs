@@ -0,0 +1,63 @@
+use anyhow::{anyhow, Result};
+use std::time::{SystemTime, UNIX_EPOCH};
+use toml::value::Datetime;
+
+/// Days since 1970-01-01 for a date in the proleptic Gregorian calendar.
+pub fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The year, month and day for a number of days since 1970-01-01.
+pub fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u8;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u8;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Formats a number of days since 1970-01-01 as YYYY-MM-DD.
+pub fn format(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Today's date, as days since 1970-01-01 (UTC).
+pub fn today() -> Result<i64> {
+    Ok((SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() / 86_400) as i64)
+}
+
+/// Converts a TOML date (or datetime, ignoring the time) into days since
+/// 1970-01-01.
+pub fn from_toml(datetime: &Datetime) -> Result<i64> {
+    let date = datetime
+        .date
+        .ok_or_else(|| anyhow!("expected a date, found {datetime}"))?;
+    Ok(days_from_civil(i64::from(date.year), date.month, date.day))
+}
+
+/// Parses a YYYY-MM-DD date into days since 1970-01-01.
+pub fn parse(text: &str) -> Result<i64> {
+    from_toml(
+        &text
+            .parse::<Datetime>()
+            .map_err(|e| anyhow!("invalid date {text}: {e}"))?,
+    )
+}
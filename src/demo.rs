@@ -0,0 +1,20 @@
+//! The example config bundled for `room-matcher demo`, so the tool can be
+//! tried before writing a config of one's own.
+
+use anyhow::Result;
+use toml_edit::Document;
+
+use crate::config::{self, Config};
+
+/// The example config: a class of 30 in four circles of friends.
+pub const CONFIG: &str = include_str!("../examples/demo.toml");
+
+/// The seed the demo is solved with, so it shows the same rooms every time.
+pub const SEED: u64 = 2024;
+
+/// How many of the best distinct solutions the demo lists.
+pub const TOP: usize = 3;
+
+pub fn config() -> Result<Config> {
+    config::parse(&CONFIG.parse::<Document>()?)
+}
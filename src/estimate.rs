@@ -0,0 +1,64 @@
+//! Cost estimates for `--estimate`: a small calibration run of the same
+//! config, scaled up to the whole solutions budget, so that an hours-long
+//! run isn't started by mistake.
+
+use std::time::Duration;
+
+use crate::logger;
+use crate::solver::{solution_size, Problem};
+
+/// How many solutions the calibration run generates at most.
+pub const CALIBRATION_SOLUTIONS: u64 = 1000;
+
+/// What a full run is expected to take.
+pub struct Estimate {
+    /// The solutions budget, reduced as the run would to stay within
+    /// `--max-memory`.
+    pub solutions: u64,
+    pub time: Duration,
+    /// Bytes taken up by the solutions kept while sampling.
+    pub memory: u64,
+}
+
+/// Scales a calibration run that generated `sampled` solutions in `took`,
+/// keeping `kept` of them, up to the full budget.
+pub fn extrapolate(
+    problem: &Problem,
+    max_memory: u64,
+    sampled: u64,
+    kept: u64,
+    took: Duration,
+) -> Estimate {
+    let size = solution_size(problem);
+    let solutions = problem.settings.solutions.min((max_memory / size).max(1));
+    let scale = solutions as f64 / sampled.max(1) as f64;
+    Estimate {
+        solutions,
+        time: took.mul_f64(scale),
+        memory: (kept as f64 * scale) as u64 * size,
+    }
+}
+
+/// A duration in hours and minutes, or minutes and seconds, once it's
+/// too long to read in seconds.
+pub fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    match seconds {
+        0..60 => logger::format_duration(time),
+        60..3600 => format!("{}m {}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {}m", seconds / 3600, seconds / 60 % 60),
+    }
+}
+
+/// A byte count with a K, M or G suffix (powers of 1024), as taken by
+/// `--max-memory`.
+pub fn format_size(bytes: u64) -> String {
+    let mut size = bytes as f64;
+    for unit in ["", "K", "M"] {
+        if size.round() < 1024.0 {
+            return format!("{size:.0}{unit}");
+        }
+        size /= 1024.0;
+    }
+    format!("{size:.1}G")
+}
@@ -0,0 +1,67 @@
+//! An exact solver for configs of up to a few hundred people. Pairing
+//! people up is a maximum-weight matching on the graph of everyone who may
+//! share, with each pair weighted by how its room ranks, so the result is
+//! the best there is under the ranking rather than the best of a sample.
+
+use anyhow::{bail, Result};
+
+use crate::blossom;
+use crate::solver::{allowed, contract, ranking_key, Problem, Solution};
+
+/// The most people solved exactly, not counting pairs pinned to the same
+/// room; the work grows with the cube of the number of people.
+pub const MAX_PEOPLE: usize = 400;
+
+/// Fails unless the exact solver can take `problem` on. Rules, room
+/// themes and `max_unpreferred` depend on more than each pair on its own,
+/// so configs using them are turned down.
+pub fn check(problem: &Problem) -> Result<()> {
+    let n = contract(problem).1.len();
+    let total = problem.people.len();
+    if n > MAX_PEOPLE {
+        bail!("the exact solver handles up to {MAX_PEOPLE} people not pinned together, but {n} of the config's {total} people are");
+    }
+    if n % 2 == 1 {
+        bail!("the exact solver needs an even number of people not pinned together, but {n} of the config's {total} people are");
+    }
+    if !problem.rules.is_empty()
+        || problem.settings.max_unpreferred.is_some()
+        || (problem.settings.theme_weight != 0.0 && !problem.themes.is_empty())
+    {
+        bail!("the exact solver doesn't support rules, room themes or max_unpreferred");
+    }
+    Ok(())
+}
+
+/// Finds the best possible solution, see [`check`] for what it takes.
+pub fn solve(problem: &Problem) -> Result<Solution> {
+    check(problem)?;
+    let (mut result, people) = contract(problem);
+    let n = people.len();
+
+    let ranking = problem.settings.ranking;
+    let mut edges = vec![];
+    for a in 0..n {
+        for b in a + 1..n {
+            let (x, y) = (&people[a], &people[b]);
+            if allowed(x, y, problem) {
+                let room = Solution::from_rooms(vec![(x.clone(), y.clone())], problem);
+                edges.push((a, b, ranking_key(&room, ranking)));
+            }
+        }
+    }
+
+    // Every pairing of everyone has the same number of rooms, so the
+    // heaviest of the largest matchings is the best pairing if there is one.
+    let mates = blossom::solve(n, &edges);
+    if mates.contains(&None) {
+        bail!("there is no way to pair everyone up that honours every room pin and veto");
+    }
+    for (a, b) in mates.into_iter().enumerate() {
+        let b = b.expect("everyone is matched");
+        if a < b {
+            result.push((people[a].clone(), people[b].clone()));
+        }
+    }
+    Ok(Solution::from_rooms(result, problem))
+}
@@ -0,0 +1,173 @@
+//! Why two people ended up sharing a room (or didn't), reconstructed from
+//! the saved rooms and the config. No trace of the solver's decisions is
+//! saved, so this points at what shaped them: pins, partners, each side's entries,
+//! what happened to the people they preferred instead, and how few others
+//! they could have shared with.
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::history::{Record, Room};
+use crate::solver::{feasible_partners, score_parts, tier, Problem, Tier};
+
+fn room_of<'a>(record: &'a Record, name: &str) -> Result<&'a Room> {
+    record
+        .rooms
+        .iter()
+        .find(|x| x.people.iter().any(|x| x == name))
+        .ok_or_else(|| anyhow!("{name} isn't in the saved rooms"))
+}
+
+fn others(room: &Room, name: &str) -> String {
+    let others = room
+        .people
+        .iter()
+        .filter(|x| *x != name)
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if others.is_empty() {
+        "no one".to_string()
+    } else {
+        others.join(" and ")
+    }
+}
+
+/// How `person` listed `other`, e.g. "Ana lists Bea as preferred".
+fn entry(person: &str, other: &str, problem: &Problem) -> String {
+    let (preferred, unpreferred) = &problem.constraints[person];
+    if preferred.iter().any(|x| x == other) {
+        format!("{person} lists {other} as preferred")
+    } else if unpreferred.iter().any(|x| x == other) {
+        format!("{person} lists {other} as unpreferred")
+    } else {
+        format!("{person} doesn't list {other}")
+    }
+}
+
+/// Why none of the people `person` preferred share a room with them:
+/// either they didn't list `person` back, or where they went instead.
+fn preferred_elsewhere(
+    person: &str,
+    partner: &str,
+    record: &Record,
+    problem: &Problem,
+) -> Vec<String> {
+    let mut lines = vec![];
+    for other in &problem.constraints[person].0 {
+        if other == partner || !problem.constraints.contains_key(other) {
+            continue;
+        }
+        if !problem.constraints[other].0.iter().any(|x| x == person) {
+            lines.push(format!(
+                "{person} prefers {other}, who doesn't list {person} back, so they couldn't make a preferred room"
+            ));
+        } else if let Ok(room) = room_of(record, other) {
+            lines.push(format!(
+                "{person} and {other} prefer each other, but {other} shares {} with {} ({})",
+                room.name,
+                others(room, other),
+                room.tier
+            ));
+        }
+    }
+    lines
+}
+
+/// The reasons, one sentence each, that `a` and `b` share a room in
+/// `record`, or where each of them is if they don't.
+pub fn explain(record: &Record, a: &str, b: &str, problem: &Problem) -> Result<Vec<String>> {
+    for name in [a, b] {
+        if !problem.constraints.contains_key(name) {
+            bail!("{name} isn't in the config");
+        }
+    }
+    let (room_a, room_b) = (room_of(record, a)?, room_of(record, b)?);
+    let mut lines = vec![];
+    if room_a.name != room_b.name {
+        for (name, room) in [(a, room_a), (b, room_b)] {
+            lines.push(format!(
+                "{name} shares {} with {} ({})",
+                room.name,
+                others(room, name),
+                room.tier
+            ));
+        }
+        lines.push(format!(
+            "{}, and {}",
+            entry(a, b, problem),
+            entry(b, a, problem)
+        ));
+        return Ok(lines);
+    }
+
+    lines.push(format!(
+        "{a} and {b} share {} ({})",
+        room_a.name, room_a.tier
+    ));
+    if let Some(pin) = problem
+        .pins
+        .get(a)
+        .filter(|x| problem.pins.get(b) == Some(*x))
+    {
+        lines.push(format!(
+            "both are pinned to \"{pin}\", so they had to share it whatever their preferences"
+        ));
+    }
+    if problem.partners.get(a).is_some_and(|x| x == b) {
+        lines.push("they are partners (partner_of or [[pins]]), so they had to share".to_string());
+    }
+    if let Some(third) = room_a.people.get(2) {
+        lines.push(format!(
+            "{third} is the third in the room, as there's an odd number of people and everyone in it allows a room of three"
+        ));
+    }
+    lines.push(entry(a, b, problem));
+    lines.push(entry(b, a, problem));
+    let pair_tier = tier(a, b, problem);
+    lines.push(match pair_tier {
+        Tier::Preferred => "they prefer each other, which makes a preferred room".to_string(),
+        Tier::Accepted => {
+            "neither marked the other unpreferred, which makes an accepted room".to_string()
+        }
+        Tier::Unpreferred => "one of them marked the other unpreferred".to_string(),
+    });
+    if pair_tier != Tier::Preferred {
+        for (person, partner) in [(a, b), (b, a)] {
+            lines.extend(preferred_elsewhere(person, partner, record, problem));
+        }
+    }
+    let minimum = problem.settings.min_feasible_partners;
+    for person in [a, b]
+        .into_iter()
+        .filter(|x| !problem.partners.contains_key(*x))
+    {
+        let count = feasible_partners(person, problem);
+        if count < minimum {
+            lines.push(format!(
+                "{person} can only share with {count} people without an unpreferred room or breaking a pin, partner, veto or role"
+            ));
+        }
+    }
+    let parts = score_parts(a, b, problem);
+    let named = [
+        ("keep current", parts.keep_current),
+        ("stale", parts.stale),
+        ("no link", parts.no_link),
+        ("disappointment", parts.disappointment),
+        ("tags", parts.tags),
+        ("ratings", parts.ratings),
+        ("popularity", parts.popularity),
+        ("newcomer", parts.newcomer),
+    ]
+    .into_iter()
+    .filter(|(_, value)| *value != 0.0)
+    .map(|(name, value)| format!("{name} {value:+.2}"))
+    .collect::<Vec<_>>();
+    if !named.is_empty() {
+        lines.push(format!(
+            "the pair adds {:+.2} to the soft score: {}",
+            parts.total(),
+            named.join(", ")
+        ));
+    }
+    Ok(lines)
+}
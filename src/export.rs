@@ -0,0 +1,125 @@
+//! The rooms as one row per person for `--export`: their room, roommate,
+//! how well the room fits them and its notes, as CSV, JSON or a plain text
+//! table, with only the columns picked with `--fields`.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use std::fs;
+use std::path::Path;
+
+use crate::houses::Member;
+
+/// What the rows are written as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Csv,
+    Json,
+    /// Columns lined up with spaces, for reading or printing.
+    Table,
+}
+
+/// A column of the rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Field {
+    Room,
+    Person,
+    /// Their roommate, or both roommates in a room of three.
+    Partner,
+    /// Whether the room is preferred, accepted or unpreferred for them.
+    Outcome,
+    /// The room's medical flag and notes.
+    Notes,
+}
+
+impl Field {
+    /// Every column, in the order they're written by default.
+    pub const ALL: [Self; 5] = [
+        Self::Room,
+        Self::Person,
+        Self::Partner,
+        Self::Outcome,
+        Self::Notes,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Room => "room",
+            Self::Person => "person",
+            Self::Partner => "partner",
+            Self::Outcome => "outcome",
+            Self::Notes => "notes",
+        }
+    }
+
+    fn value(self, member: &Member) -> String {
+        match self {
+            Self::Room => member.room.clone(),
+            Self::Person => member.name.clone(),
+            Self::Partner => member.roommates.clone(),
+            Self::Outcome => member.tier.to_string(),
+            Self::Notes => {
+                let medical = member.medical.then(|| "[medical]".to_string());
+                medical
+                    .into_iter()
+                    .chain(member.notes.iter().cloned())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            }
+        }
+    }
+}
+
+/// Writes a row per member to `path`, with the columns in `fields` in the
+/// order given.
+pub fn write(path: &Path, members: &[Member], fields: &[Field], format: Format) -> Result<()> {
+    let rows = members
+        .iter()
+        .map(|member| fields.iter().map(|x| x.value(member)).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let header = fields.iter().map(|x| x.name()).collect::<Vec<_>>();
+    match format {
+        Format::Csv => {
+            let mut writer = csv::Writer::from_path(path)?;
+            writer.write_record(&header)?;
+            for row in &rows {
+                writer.write_record(row)?;
+            }
+            writer.flush()?;
+        }
+        Format::Json => {
+            let rows = rows
+                .into_iter()
+                .map(|row| header.iter().copied().zip(row).collect::<IndexMap<_, _>>())
+                .collect::<Vec<_>>();
+            fs::write(path, serde_json::to_string_pretty(&rows)? + "\n")?;
+        }
+        Format::Table => fs::write(path, table(&header, &rows))?,
+    }
+    Ok(())
+}
+
+/// Lines up `rows` under `header`, two spaces between columns.
+fn table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths = header.iter().map(|x| x.chars().count()).collect::<Vec<_>>();
+    for row in rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+    let line = |values: Vec<&str>| {
+        let mut line = values
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{value:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        line.truncate(line.trim_end().len());
+        line + "\n"
+    };
+    let mut text = line(header.to_vec());
+    for row in rows {
+        text += &line(row.iter().map(String::as_str).collect());
+    }
+    text
+}
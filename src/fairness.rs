@@ -0,0 +1,125 @@
+//! How well the rooms suit each group of people sharing a value of some
+//! attribute (e.g. gender or year), to check that no group is
+//! systematically worse off, and the `[fairness]` cap on the gap.
+
+use crate::collate;
+use crate::solver::{third_tier, tier, Problem, Solution, Tier};
+
+/// Everyone with one value of the attribute, and how their rooms suit
+/// them.
+pub struct Group {
+    /// The value, or None for people without one.
+    pub value: Option<String>,
+    pub people: usize,
+    pub preferred: usize,
+    pub accepted: usize,
+    pub unpreferred: usize,
+}
+
+impl Group {
+    /// The share of the group with a room of `tier`, from 0 to 1.
+    pub fn share(&self, tier: Tier) -> f64 {
+        let count = match tier {
+            Tier::Preferred => self.preferred,
+            Tier::Accepted => self.accepted,
+            Tier::Unpreferred => self.unpreferred,
+        };
+        count as f64 / self.people as f64
+    }
+}
+
+/// `person`'s value of `attribute`: one of their `attributes`, or their
+/// `house` or `role`.
+pub fn value<'a>(person: &str, attribute: &str, problem: &'a Problem) -> Option<&'a str> {
+    let own = problem
+        .attributes
+        .get(person)
+        .and_then(|x| x.get(attribute));
+    let builtin = match attribute {
+        "house" => problem.houses.get(person),
+        "role" => problem.roles.get(person),
+        _ => None,
+    };
+    own.or(builtin).map(String::as_str)
+}
+
+/// How well their room suits each person in `solution`, as in
+/// [`crate::houses::Member::tier`].
+fn tiers<'a>(solution: &'a Solution, problem: &Problem) -> Vec<(&'a str, Tier)> {
+    let mut tiers = vec![];
+    for (i, (a, b)) in solution.result.iter().enumerate() {
+        match solution.third.as_ref().filter(|x| x.0 == i) {
+            Some((_, c)) => {
+                let pair = (a.clone(), b.clone());
+                tiers.push((a.as_str(), third_tier(a, &(b.clone(), c.clone()), problem)));
+                tiers.push((b.as_str(), third_tier(b, &(a.clone(), c.clone()), problem)));
+                tiers.push((c.as_str(), third_tier(c, &pair, problem)));
+            }
+            None => {
+                let pair_tier = tier(a, b, problem);
+                tiers.extend([(a.as_str(), pair_tier), (b.as_str(), pair_tier)]);
+            }
+        }
+    }
+    tiers
+}
+
+/// Everyone in `solution` grouped by their value of `attribute`, in value
+/// order, with anyone without one last.
+pub fn audit(solution: &Solution, attribute: &str, problem: &Problem) -> Vec<Group> {
+    let mut groups = Vec::<Group>::new();
+    for (person, tier) in tiers(solution, problem) {
+        let value = value(person, attribute, problem).map(str::to_string);
+        let index = match groups.iter().position(|x| x.value == value) {
+            Some(i) => i,
+            None => {
+                groups.push(Group {
+                    value,
+                    people: 0,
+                    preferred: 0,
+                    accepted: 0,
+                    unpreferred: 0,
+                });
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[index];
+        group.people += 1;
+        match tier {
+            Tier::Preferred => group.preferred += 1,
+            Tier::Accepted => group.accepted += 1,
+            Tier::Unpreferred => group.unpreferred += 1,
+        }
+    }
+    groups.sort_by(|a, b| match (&a.value, &b.value) {
+        (Some(a), Some(b)) => collate::compare(a, b),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+    groups
+}
+
+/// The largest difference in the share of people with a preferred room
+/// between any two groups with a value, from 0 to 1.
+pub fn gap(groups: &[Group]) -> f64 {
+    let shares = groups
+        .iter()
+        .filter(|x| x.value.is_some())
+        .map(|x| x.share(Tier::Preferred))
+        .collect::<Vec<_>>();
+    let highest = shares.iter().copied().fold(f64::MIN, f64::max);
+    let lowest = shares.iter().copied().fold(f64::MAX, f64::min);
+    if shares.is_empty() {
+        0.0
+    } else {
+        highest - lowest
+    }
+}
+
+/// Whether `solution` stays within the `[fairness]` cap, if there is one.
+pub fn within(solution: &Solution, problem: &Problem) -> bool {
+    problem
+        .fairness
+        .as_ref()
+        .and_then(|x| x.cap())
+        .is_none_or(|(attribute, max_gap)| gap(&audit(solution, attribute, problem)) <= max_gap)
+}
@@ -0,0 +1,96 @@
+use anyhow::{bail, Result};
+use rand::seq::SliceRandom;
+use rand::Rng as _;
+use toml_edit::{value, Array, Document, Item, Table};
+
+use crate::migrate::CURRENT_SCHEMA_VERSION;
+use crate::rng::Streams;
+
+/// The shape of a generated config.
+pub struct Options {
+    pub people: usize,
+    /// The chance that someone lists a given member of their own cluster
+    /// as preferred.
+    pub density: f64,
+    pub clusters: usize,
+    /// The chance that someone lists a given person outside their cluster
+    /// as unpreferred.
+    pub avoid_density: f64,
+    /// The chance that a preference is returned by the other person, on
+    /// top of them picking it independently.
+    pub mutual: f64,
+    pub seed: u64,
+}
+
+/// Generates a random config in which people fall into clusters of
+/// friends: preferences stay inside a cluster and are often mutual, while
+/// a few people outside it are marked unpreferred.
+pub fn generate(options: &Options) -> Result<Document> {
+    if options.clusters == 0 || options.clusters > options.people.max(1) {
+        bail!("--clusters must be between 1 and the number of people");
+    }
+    for (name, chance) in [
+        ("--density", options.density),
+        ("--avoid-density", options.avoid_density),
+        ("--mutual", options.mutual),
+    ] {
+        if !(0.0..=1.0).contains(&chance) {
+            bail!("{name} must be between 0 and 1");
+        }
+    }
+    let mut rng = Streams::new(options.seed).get("gen", 0);
+
+    let width = options.people.to_string().len();
+    let names = (1..=options.people)
+        .map(|i| format!("p{i:0width$}"))
+        .collect::<Vec<_>>();
+    let mut order = (0..options.people).collect::<Vec<_>>();
+    order.shuffle(&mut rng);
+    let mut cluster = vec![0; options.people];
+    for (i, person) in order.into_iter().enumerate() {
+        cluster[person] = i % options.clusters;
+    }
+
+    let mut preferred = vec![vec![false; options.people]; options.people];
+    let mut unpreferred = vec![vec![false; options.people]; options.people];
+    for a in 0..options.people {
+        for b in 0..options.people {
+            if a == b {
+                continue;
+            }
+            if cluster[a] == cluster[b] {
+                if rng.gen_bool(options.density) {
+                    preferred[a][b] = true;
+                    if rng.gen_bool(options.mutual) {
+                        preferred[b][a] = true;
+                    }
+                }
+            } else if rng.gen_bool(options.avoid_density) {
+                unpreferred[a][b] = true;
+            }
+        }
+    }
+
+    let mut doc = Document::new();
+    let mut config = Table::new();
+    config.insert("solutions", value(10_000));
+    config.insert("schema_version", value(CURRENT_SCHEMA_VERSION));
+    doc.insert("config", Item::Table(config));
+    let mut people = Table::new();
+    people.set_implicit(true);
+    let list = |row: &[bool]| {
+        row.iter()
+            .zip(&names)
+            .filter(|(x, _)| **x)
+            .map(|(_, name)| name.as_str())
+            .collect::<Array>()
+    };
+    for (i, name) in names.iter().enumerate() {
+        let mut person = Table::new();
+        person.insert("preferred", value(list(&preferred[i])));
+        person.insert("unpreferred", value(list(&unpreferred[i])));
+        people.insert(name, Item::Table(person));
+    }
+    doc.insert("people", Item::Table(people));
+    Ok(doc)
+}
@@ -0,0 +1,278 @@
+use anyhow::{anyhow, bail, Result};
+use indexmap::{IndexMap, IndexSet};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::Path;
+
+use crate::index;
+use crate::solver::{occupants, room_notes, room_tier, Problem, Solution, Tier};
+use crate::storage::{self, Storage};
+
+/// The schema version of saved solutions and history records written by
+/// this build. It only goes up when a change would break existing
+/// readers; new optional fields may appear at any time. Records without a
+/// version are version 1, from before records were versioned.
+pub const SCHEMA_VERSION: i64 = 2;
+
+fn unversioned() -> i64 {
+    1
+}
+
+/// A room as it is saved to disk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Room {
+    pub name: String,
+    pub people: Vec<String>,
+    pub tier: Tier,
+    /// Whether anyone in the room has `medical` set.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub medical: bool,
+    /// The occupants' `notes`, e.g. "Ana: nut allergy".
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+impl Room {
+    /// Sets `medical` and `notes` from the current occupants.
+    pub fn annotate(&mut self, problem: &Problem) {
+        (self.medical, self.notes) = room_notes(&self.people.iter().collect::<Vec<_>>(), problem);
+    }
+}
+
+/// A solution as it is saved to disk, both by `--save` and as one line of
+/// the `--history` file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Record {
+    /// [`SCHEMA_VERSION`] when the record was written.
+    #[serde(default = "unversioned")]
+    pub schema_version: i64,
+    pub date: String,
+    pub seed: u64,
+    pub preferred: usize,
+    pub accepted: usize,
+    pub unpreferred: usize,
+    pub rooms: Vec<Room>,
+    /// The `id` of each person in the rooms who has one.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub ids: BTreeMap<String, String>,
+}
+
+impl Record {
+    pub fn new(solution: &Solution, problem: &Problem, date: String, seed: u64) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            date,
+            seed,
+            preferred: solution.preferred,
+            accepted: solution.accepted,
+            unpreferred: solution.unpreferred,
+            rooms: occupants(solution, problem)
+                .into_iter()
+                .map(|(name, people)| {
+                    let (medical, notes) = room_notes(&people, problem);
+                    Room {
+                        name,
+                        tier: room_tier(&people, problem),
+                        people: people.into_iter().cloned().collect(),
+                        medical,
+                        notes,
+                    }
+                })
+                .collect(),
+            ids: problem
+                .people
+                .iter()
+                .filter_map(|x| Some((x.clone(), problem.ids.get(x)?.clone())))
+                .collect(),
+        }
+    }
+
+    /// Who `name` is across records: their `id`, or their name without one.
+    pub fn identity<'a>(&'a self, name: &'a str) -> &'a str {
+        self.ids.get(name).map_or(name, String::as_str)
+    }
+
+    /// The rooms as a solution to `problem`, rescored against it. Anyone on
+    /// their own is left out.
+    pub fn solution(&self, problem: &Problem) -> Result<Solution> {
+        let mut result = vec![];
+        let mut third = None;
+        for room in &self.rooms {
+            if let Some(person) = room
+                .people
+                .iter()
+                .find(|x| !problem.constraints.contains_key(*x))
+            {
+                bail!("{person} is in the saved rooms, but not in the config");
+            }
+            match room.people.as_slice() {
+                [_] => {}
+                [a, b] => result.push((a.clone(), b.clone())),
+                [a, b, c] => {
+                    third = Some((result.len(), c.clone()));
+                    result.push((a.clone(), b.clone()));
+                }
+                _ => bail!("{} holds {} people", room.name, room.people.len()),
+            }
+        }
+        Ok(Solution::with_third(result, third, problem))
+    }
+
+    /// The room the person known as `identity` (see [`Record::identity`])
+    /// was put in, if any.
+    pub fn room_of(&self, identity: &str) -> Option<&Room> {
+        self.rooms
+            .iter()
+            .find(|x| x.people.iter().any(|x| self.identity(x) == identity))
+    }
+
+    /// Reads a record written by this or any earlier build, upgrading it
+    /// to [`SCHEMA_VERSION`].
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut value = serde_json::from_str::<serde_json::Value>(text)?;
+        let original = match value.get("schema_version") {
+            Some(version) => version
+                .as_i64()
+                .ok_or_else(|| anyhow!("schema_version must be an integer"))?,
+            None => unversioned(),
+        };
+        if original > SCHEMA_VERSION {
+            bail!("the record uses schema version {original}, but this build only understands up to {SCHEMA_VERSION}");
+        }
+        let mut version = original;
+        while version < SCHEMA_VERSION {
+            match version {
+                // Version 1 only lacked schema_version itself; every field
+                // added since is optional.
+                1 => {}
+                _ => unreachable!(),
+            }
+            version += 1;
+        }
+        value["schema_version"] = version.into();
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn load(storage: &Storage, path: &Path) -> Result<Self> {
+        Self::parse(&storage.read(path)?)
+    }
+
+    pub fn save(&self, storage: &Storage, path: &Path) -> Result<()> {
+        storage.write(path, &serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Replaces the whole of a history file with `records`.
+pub fn write(storage: &Storage, path: &Path, records: &[Record]) -> Result<()> {
+    let mut text = String::new();
+    for record in records {
+        text.push_str(&serde_json::to_string(record)?);
+        text.push('\n');
+    }
+    storage.write(path, &text)?;
+    index::discard(path)
+}
+
+/// Adds a record to the end of a history file, creating it if needed.
+pub fn append(storage: &Storage, path: &Path, record: &Record) -> Result<()> {
+    storage.append(path, &serde_json::to_string(record)?)
+}
+
+/// The rooms each person (by [`Record::identity`]) was in across
+/// `records`, oldest first.
+pub fn past_rooms(records: &[Record]) -> IndexMap<String, Vec<String>> {
+    let mut rooms = IndexMap::<String, Vec<String>>::new();
+    for record in records {
+        for room in &record.rooms {
+            for person in &room.people {
+                rooms
+                    .entry(record.identity(person).to_string())
+                    .or_default()
+                    .push(room.name.clone());
+            }
+        }
+    }
+    rooms
+}
+
+/// The people in `problem` who are in fewer than `runs` of `records`, by
+/// [`Problem::identity`]. Nobody is new to an empty history.
+pub fn newcomers(records: &[Record], runs: usize, problem: &Problem) -> IndexSet<String> {
+    if records.is_empty() {
+        return IndexSet::new();
+    }
+    let past = past_rooms(records);
+    problem
+        .people
+        .iter()
+        .filter(|x| past.get(problem.identity(x)).map_or(0, Vec::len) < runs)
+        .cloned()
+        .collect()
+}
+
+/// Reads every record in a history file, oldest first.
+pub fn read(storage: &Storage, path: &Path) -> Result<Vec<Record>> {
+    storage
+        .read(path)?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            Record::parse(line).map_err(|e| anyhow!("{}:{}: {e}", path.display(), i + 1))
+        })
+        .collect()
+}
+
+/// Which runs of a history file to read.
+#[derive(Debug, Default)]
+pub struct Selection {
+    /// Only runs dated on or after this, as YYYY-MM-DD.
+    pub since: Option<String>,
+    /// Only the latest this many runs (of those since `since`).
+    pub last: Option<usize>,
+}
+
+impl Selection {
+    /// The positions of the selected runs among runs with these dates.
+    fn pick<'a>(&self, dates: impl Iterator<Item = &'a str>) -> Vec<usize> {
+        let picked = dates
+            .enumerate()
+            .filter(|(_, date)| self.since.as_deref().is_none_or(|x| *date >= x))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        let skip = picked.len().saturating_sub(self.last.unwrap_or(usize::MAX));
+        picked[skip..].to_vec()
+    }
+}
+
+/// Calls `f` with each selected record of a history file, oldest first,
+/// one at a time. Plain files are read through their index (see
+/// [`index`]), so only the selected runs are parsed; encrypted ones have
+/// to be decrypted whole first.
+pub fn stream(
+    storage: &Storage,
+    path: &Path,
+    selection: &Selection,
+    mut f: impl FnMut(Record),
+) -> Result<()> {
+    if !storage::is_plain(path)? {
+        let records = read(storage, path)?;
+        let picked = selection.pick(records.iter().map(|x| x.date.as_str()));
+        for (i, record) in records.into_iter().enumerate() {
+            if picked.binary_search(&i).is_ok() {
+                f(record);
+            }
+        }
+        return Ok(());
+    }
+    let entries = index::load(path)?;
+    let mut file = File::open(path)?;
+    for i in selection.pick(entries.iter().map(|x| x.date.as_str())) {
+        let record = index::read(&mut file, &entries[i])
+            .map_err(|e| anyhow!("{}: run {}: {e}", path.display(), i + 1))?;
+        f(record);
+    }
+    Ok(())
+}
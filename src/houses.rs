@@ -0,0 +1,88 @@
+//! The rooms grouped by each person's `house`, so that whoever looks after
+//! a house (or form, or any other unit) can review their own people.
+
+use crate::collate;
+use crate::solver::{occupants, room_notes, third_tier, tier, Problem, Solution, Tier};
+
+/// One person's room, as seen from their house.
+pub struct Member {
+    pub name: String,
+    pub room: String,
+    /// Their roommates, e.g. "Ana and Bea" in a room of three.
+    pub roommates: String,
+    /// How well their room fits them: unpreferred if they and any roommate
+    /// marked the other unpreferred, otherwise preferred if they and any
+    /// roommate preferred each other.
+    pub tier: Tier,
+    /// The room's medical flag and notes, see [`room_notes`].
+    pub medical: bool,
+    pub notes: Vec<String>,
+}
+
+pub struct House {
+    /// The house's name, or None for people without one.
+    pub name: Option<String>,
+    pub members: Vec<Member>,
+}
+
+impl House {
+    /// How many members got a room of `tier`.
+    pub fn count(&self, tier: Tier) -> usize {
+        self.members.iter().filter(|x| x.tier == tier).count()
+    }
+}
+
+/// Everyone in `solution`, room by room.
+pub fn members(solution: &Solution, problem: &Problem) -> Vec<Member> {
+    let mut members = vec![];
+    for (room, people) in occupants(solution, problem) {
+        let (medical, notes) = room_notes(&people, problem);
+        for person in &people {
+            let others = people
+                .iter()
+                .filter(|x| *x != person)
+                .map(|x| (*x).clone())
+                .collect::<Vec<_>>();
+            let tier = match others.as_slice() {
+                [other] => tier(person, other, problem),
+                [a, b] => third_tier(person, &(a.clone(), b.clone()), problem),
+                _ => Tier::Accepted,
+            };
+            members.push(Member {
+                name: (*person).clone(),
+                room: room.clone(),
+                roommates: others.join(" and "),
+                tier,
+                medical,
+                notes: notes.clone(),
+            });
+        }
+    }
+    members
+}
+
+/// Everyone in `solution`, grouped by house in name order, with anyone
+/// without a house last.
+pub fn group(solution: &Solution, problem: &Problem) -> Vec<House> {
+    let mut houses = Vec::<House>::new();
+    for member in members(solution, problem) {
+        let name = problem.houses.get(&member.name).cloned();
+        match houses.iter_mut().find(|x| x.name == name) {
+            Some(house) => house.members.push(member),
+            None => houses.push(House {
+                name,
+                members: vec![member],
+            }),
+        }
+    }
+    for house in &mut houses {
+        house
+            .members
+            .sort_by(|a, b| collate::compare(&a.name, &b.name));
+    }
+    houses.sort_by(|a, b| match (&a.name, &b.name) {
+        (Some(a), Some(b)) => collate::compare(a, b),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+    houses
+}
@@ -0,0 +1,67 @@
+/// Solves the assignment problem for a cost matrix with at least as many
+/// columns as rows, returning the column given to each row so that the
+/// total cost is minimal. Runs in O(rows² · columns).
+pub fn solve(cost: &[Vec<f64>]) -> Vec<usize> {
+    let rows = cost.len();
+    let columns = cost.first().map_or(0, Vec::len);
+
+    // Potentials for rows and columns, and the row matched to each column.
+    // Everything is 1-indexed so that index 0 can act as a virtual start.
+    let mut u = vec![0.0; rows + 1];
+    let mut v = vec![0.0; columns + 1];
+    let mut matched = vec![0; columns + 1];
+    let mut way = vec![0; columns + 1];
+
+    for row in 1..=rows {
+        matched[0] = row;
+        let mut column = 0;
+        let mut min_slack = vec![f64::INFINITY; columns + 1];
+        let mut used = vec![false; columns + 1];
+        loop {
+            used[column] = true;
+            let current_row = matched[column];
+            let mut delta = f64::INFINITY;
+            let mut next = 0;
+            for j in 1..=columns {
+                if used[j] {
+                    continue;
+                }
+                let slack = cost[current_row - 1][j - 1] - u[current_row] - v[j];
+                if slack < min_slack[j] {
+                    min_slack[j] = slack;
+                    way[j] = column;
+                }
+                if min_slack[j] < delta {
+                    delta = min_slack[j];
+                    next = j;
+                }
+            }
+            for j in 0..=columns {
+                if used[j] {
+                    u[matched[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min_slack[j] -= delta;
+                }
+            }
+            column = next;
+            if matched[column] == 0 {
+                break;
+            }
+        }
+        // Walk back along the augmenting path, flipping the matching.
+        while column != 0 {
+            let previous = way[column];
+            matched[column] = matched[previous];
+            column = previous;
+        }
+    }
+
+    let mut assignment = vec![0; rows];
+    for (column, &row) in matched.iter().enumerate().skip(1) {
+        if row != 0 {
+            assignment[row - 1] = column - 1;
+        }
+    }
+    assignment
+}
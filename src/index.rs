@@ -0,0 +1,128 @@
+//! An index of a history file, kept next to it as `<file>.index`, so that
+//! `history analytics` can go straight to the runs it needs instead of
+//! parsing every line of years of history. History is only ever added to,
+//! so the index is brought up to date by indexing whatever was added since
+//! it was last used. Rewriting the history in place (e.g. with
+//! `propose-swap --apply`) discards the index, and it is rebuilt the next
+//! time it's needed.
+
+use anyhow::{anyhow, Result};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::history::Record;
+use crate::storage::Storage;
+
+/// How every index file starts.
+const HEADER: &str = "# room-matcher history index 1";
+
+/// Where one run is in the history file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// The byte the run's line starts at.
+    pub offset: u64,
+    /// The length of the line, without its newline.
+    pub len: u64,
+    pub date: String,
+}
+
+/// Where the index of `history` is kept.
+pub fn path(history: &Path) -> PathBuf {
+    let mut name = history.as_os_str().to_owned();
+    name.push(".index");
+    name.into()
+}
+
+/// Deletes the index of `history`, if it has one, after the history has
+/// been rewritten.
+pub fn discard(history: &Path) -> Result<()> {
+    match fs::remove_file(path(history)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// The entries in an index file, or None if it can't be read.
+fn parse(text: &str) -> Option<Vec<Entry>> {
+    let mut lines = text.lines();
+    if lines.next()? != HEADER {
+        return None;
+    }
+    lines
+        .map(|line| {
+            let mut fields = line.splitn(3, ' ');
+            Some(Entry {
+                offset: fields.next()?.parse().ok()?,
+                len: fields.next()?.parse().ok()?,
+                date: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Reads the run at `entry` from the history file.
+pub fn read(file: &mut File, entry: &Entry) -> Result<Record> {
+    file.seek(SeekFrom::Start(entry.offset))?;
+    let mut line = vec![0; entry.len as usize];
+    file.read_exact(&mut line)?;
+    Record::parse(std::str::from_utf8(&line)?)
+}
+
+/// The index of a plain history file, updated with any runs added since it
+/// was last used, or built from scratch if it's missing or no longer fits
+/// the file.
+pub fn load(history: &Path) -> Result<Vec<Entry>> {
+    let index_path = path(history);
+    let mut file = File::open(history).map_err(|e| anyhow!("{}: {e}", history.display()))?;
+    let length = file.metadata()?.len();
+    let mut entries = fs::read_to_string(&index_path)
+        .ok()
+        .and_then(|x| parse(&x))
+        .unwrap_or_default();
+    // The last run must still be where it was; if it isn't, the file was
+    // changed by something other than adding to it.
+    let fits = match entries.last() {
+        Some(last) => {
+            last.offset + last.len <= length
+                && read(&mut file, last).is_ok_and(|x| x.date == last.date)
+        }
+        None => true,
+    };
+    if !fits {
+        entries.clear();
+    }
+    let indexed = entries.len();
+    let mut offset = entries.last().map_or(0, |x| x.offset + x.len);
+    file.seek(SeekFrom::Start(offset))?;
+    let mut reader = BufReader::new(file);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let read = reader.read_line(&mut line)? as u64;
+        if read == 0 {
+            break;
+        }
+        let text = line.trim_end_matches(['\n', '\r']);
+        let start = offset + (text.len() - text.trim_start().len()) as u64;
+        offset += read;
+        if text.trim().is_empty() {
+            continue;
+        }
+        let record = Record::parse(text.trim_start())
+            .map_err(|e| anyhow!("{}: the run at byte {start}: {e}", history.display()))?;
+        entries.push(Entry {
+            offset: start,
+            len: text.trim_start().len() as u64,
+            date: record.date,
+        });
+    }
+    if entries.len() != indexed || !fits {
+        let mut text = format!("{HEADER}\n");
+        for entry in &entries {
+            text.push_str(&format!("{} {} {}\n", entry.offset, entry.len, entry.date));
+        }
+        Storage::default().write(&index_path, &text)?;
+    }
+    Ok(entries)
+}
@@ -0,0 +1,222 @@
+//! A neutral JSON problem format for `export-problem` and
+//! `import-problem`, so instances can be passed to and from other
+//! solvers, e.g. for a shared benchmark. It only holds what any roommate
+//! solver can use: people and how many they may share a room with, a
+//! weight from each person to each other they have a view on, and the
+//! rooms with their capacities.
+//!
+//! Preferred people are exported with a weight of 1, unpreferred ones with
+//! -1, and `weights` entries as they are. Importing goes the other way:
+//! 1 or more lists someone as preferred, -1 or less as unpreferred, and
+//! anything in between becomes a `weights` entry.
+
+use anyhow::{bail, Result};
+use indexmap::{IndexMap, IndexSet};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use toml_edit::{value, Array, Document, InlineTable, Item, Table};
+
+use crate::migrate::CURRENT_SCHEMA_VERSION;
+use crate::solver::{weight, Problem};
+
+/// The schema version of instances written by this build.
+pub const SCHEMA_VERSION: i64 = 1;
+
+/// A roommate problem, independent of any one solver's config.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Instance {
+    /// [`SCHEMA_VERSION`] when the instance was written.
+    pub schema_version: i64,
+    pub people: Vec<Person>,
+    /// How much each person would like to share with another, from -1
+    /// (rather not) to 1 (would like to). Pairs left out count as 0.
+    #[serde(default)]
+    pub pair_weights: Vec<PairWeight>,
+    /// The rooms to fill, in order. Without any, rooms are numbered.
+    #[serde(default)]
+    pub rooms: Vec<Room>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Person {
+    pub name: String,
+    /// The most people this person may share a room with, themselves
+    /// included: 2, or 3 if they may be one of three.
+    pub capacity: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PairWeight {
+    pub from: String,
+    pub to: String,
+    pub weight: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Room {
+    pub name: String,
+    /// How many people the room holds, from its `size` or `room_size`.
+    pub capacity: usize,
+}
+
+/// Describes `problem` as an instance.
+pub fn export(problem: &Problem) -> Instance {
+    let people = problem
+        .people
+        .iter()
+        .map(|name| Person {
+            name: name.clone(),
+            capacity: if problem.allow_triple.contains(name) {
+                3
+            } else {
+                2
+            },
+        })
+        .collect();
+    let mut pair_weights = vec![];
+    for from in &problem.people {
+        let (preferred, unpreferred) = &problem.constraints[from];
+        let listed = preferred.iter().map(|x| (x, 1.0));
+        let listed = listed.chain(unpreferred.iter().map(|x| (x, -1.0)));
+        let rated = problem.weights.get(from).into_iter().flatten();
+        let rated = rated
+            .filter(|(to, _)| !preferred.contains(to) && !unpreferred.contains(to))
+            .map(|(to, _)| (to, weight(from, to, problem)));
+        for (to, weight) in listed.chain(rated) {
+            pair_weights.push(PairWeight {
+                from: from.clone(),
+                to: to.clone(),
+                weight,
+            });
+        }
+    }
+    Instance {
+        schema_version: SCHEMA_VERSION,
+        people,
+        pair_weights,
+        rooms: problem
+            .rooms
+            .iter()
+            .map(|name| Room {
+                name: name.clone(),
+                capacity: problem
+                    .room_sizes
+                    .get(name)
+                    .copied()
+                    .unwrap_or(problem.settings.room_size),
+            })
+            .collect(),
+    }
+}
+
+/// Builds a config from `instance`, with `rating_weight` set if any
+/// weights are left over after the preferences.
+pub fn import(instance: &Instance) -> Result<Document> {
+    if instance.schema_version > SCHEMA_VERSION {
+        bail!(
+            "the instance uses schema version {}, but this build only understands up to {SCHEMA_VERSION}",
+            instance.schema_version
+        );
+    }
+    let mut names = IndexSet::new();
+    for person in &instance.people {
+        if !names.insert(person.name.as_str()) {
+            bail!("{} is listed more than once", person.name);
+        }
+        if !(2..=3).contains(&person.capacity) {
+            bail!(
+                "{} has a capacity of {}, but rooms hold 2 people, or 3 for one room",
+                person.name,
+                person.capacity
+            );
+        }
+    }
+    let mut given = IndexMap::<&str, IndexMap<&str, f64>>::new();
+    for pair in &instance.pair_weights {
+        for name in [&pair.from, &pair.to] {
+            if !names.contains(name.as_str()) {
+                bail!("{name} has a pair weight, but isn't one of the people");
+            }
+        }
+        if pair.from == pair.to {
+            bail!("{} has a pair weight with themselves", pair.from);
+        }
+        let weights = given.entry(&pair.from).or_default();
+        if weights.insert(&pair.to, pair.weight).is_some() {
+            bail!(
+                "the weight from {} to {} is given more than once",
+                pair.from,
+                pair.to
+            );
+        }
+    }
+    if let Some(room) = instance.rooms.iter().find(|x| x.capacity < 2) {
+        bail!(
+            "{} has a capacity of {}, but rooms hold at least 2 people",
+            room.name,
+            room.capacity
+        );
+    }
+
+    let rated = given
+        .values()
+        .flatten()
+        .any(|(_, x)| x.abs() < 1.0 && *x != 0.0);
+    let mut doc = Document::new();
+    let mut config = Table::new();
+    config.insert("solutions", value(10_000));
+    config.insert("schema_version", value(CURRENT_SCHEMA_VERSION));
+    if rated {
+        config.insert("rating_weight", value(1.0));
+    }
+    doc.insert("config", Item::Table(config));
+    if !instance.rooms.is_empty() {
+        let mut rooms = Table::new();
+        rooms.set_implicit(true);
+        for room in &instance.rooms {
+            let mut table = Table::new();
+            if room.capacity != 2 {
+                table.insert("size", value(room.capacity as i64));
+            }
+            rooms.insert(&room.name, Item::Table(table));
+        }
+        doc.insert("rooms", Item::Table(rooms));
+    }
+    let mut people = Table::new();
+    people.set_implicit(true);
+    for person in &instance.people {
+        let weights = given.get(person.name.as_str());
+        let list = |keep: &dyn Fn(f64) -> bool| {
+            weights
+                .into_iter()
+                .flatten()
+                .filter(|(_, weight)| keep(**weight))
+                .map(|(name, _)| *name)
+                .collect::<Array>()
+        };
+        let mut table = Table::new();
+        for (key, list) in [
+            ("preferred", list(&|x| x >= 1.0)),
+            ("unpreferred", list(&|x| x <= -1.0)),
+        ] {
+            if !list.is_empty() {
+                table.insert(key, value(list));
+            }
+        }
+        let rated = weights
+            .into_iter()
+            .flatten()
+            .filter(|(_, weight)| weight.abs() < 1.0 && **weight != 0.0)
+            .map(|(name, weight)| (*name, *weight))
+            .collect::<InlineTable>();
+        if !rated.is_empty() {
+            table.insert("weights", value(rated));
+        }
+        if person.capacity == 3 {
+            table.insert("allow_triple", value(true));
+        }
+        people.insert(&person.name, Item::Table(table));
+    }
+    doc.insert("people", Item::Table(people));
+    Ok(doc)
+}
@@ -0,0 +1,87 @@
+use anyhow::{bail, Result};
+
+/// One piece of a room naming scheme.
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    /// The room's number, counting from 1, zero-padded to a width.
+    Number(usize),
+    /// The room's letter: A to Z, then AA, AB and so on.
+    Letter,
+}
+
+/// How rooms are labelled when the config has no `[rooms]`, e.g.
+/// `"Cabin {letter}"` or `"Dorm {number:02}"`.
+#[derive(Debug, Clone)]
+pub struct Scheme {
+    parts: Vec<Part>,
+}
+
+impl Default for Scheme {
+    fn default() -> Self {
+        Self {
+            parts: vec![Part::Text("ROOM ".to_string()), Part::Number(0)],
+        }
+    }
+}
+
+impl Scheme {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parts = vec![];
+        let mut rest = text;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            let Some(end) = rest[start..].find('}') else {
+                bail!("room_names \"{text}\" has an unclosed {{");
+            };
+            let placeholder = &rest[start + 1..start + end];
+            parts.push(match placeholder.split_once(':') {
+                None if placeholder == "number" => Part::Number(0),
+                None if placeholder == "letter" => Part::Letter,
+                Some(("number", width)) if width.starts_with('0') => match width.parse() {
+                    Ok(width) => Part::Number(width),
+                    Err(_) => bail!("room_names \"{text}\" has an invalid width in {{{placeholder}}}"),
+                },
+                _ => bail!(
+                    "room_names \"{text}\" has an unknown placeholder {{{placeholder}}}; use {{number}}, {{number:02}} or {{letter}}"
+                ),
+            });
+            rest = &rest[start + end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+        if !parts.iter().any(|x| !matches!(x, Part::Text(_))) {
+            bail!(
+                "room_names \"{text}\" needs {{number}} or {{letter}} so rooms get different names"
+            );
+        }
+        Ok(Self { parts })
+    }
+
+    /// The label of the room at `index`, counting from 0.
+    pub fn label(&self, index: usize) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(x) => x.clone(),
+                Part::Number(width) => format!("{:0width$}", index + 1),
+                Part::Letter => letters(index),
+            })
+            .collect()
+    }
+}
+
+/// Spreadsheet-style column letters: A to Z, then AA, AB and so on.
+fn letters(index: usize) -> String {
+    let mut index = index + 1;
+    let mut letters = vec![];
+    while index > 0 {
+        index -= 1;
+        letters.push(char::from(b'A' + (index % 26) as u8));
+        index /= 26;
+    }
+    letters.iter().rev().collect()
+}
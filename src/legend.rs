@@ -0,0 +1,148 @@
+//! What the numbers under RESULT mean for `--legend`, spelled out for the
+//! config in use, since the ranking and soft weights can be set so that
+//! the same counts mean rather different rooms.
+
+use crate::config::Ranking;
+use crate::solver::Problem;
+
+/// A weight with its sign, e.g. "+0.5" or "-2".
+fn signed(weight: f64) -> String {
+    if weight < 0.0 {
+        weight.to_string()
+    } else {
+        format!("+{weight}")
+    }
+}
+
+/// What goes into the soft score under `problem`'s weights, one part per
+/// weight that isn't 0.
+fn soft_score(problem: &Problem) -> Vec<String> {
+    let settings = &problem.settings;
+    let mut parts = vec![];
+    let mut add = |weight: f64, what: &str| {
+        if weight != 0.0 {
+            parts.push(format!("{} {what}", signed(weight)));
+        }
+    };
+    add(
+        settings.keep_current_weight,
+        "for each pair from --current sharing again",
+    );
+    add(
+        -settings.stale_penalty,
+        "for each preferred room relying on stale preferences",
+    );
+    add(
+        -settings.no_link_penalty,
+        "for each room where neither listed the other as preferred",
+    );
+    add(
+        -settings.disappointment_penalty,
+        "for each person sharing with none of the people they preferred",
+    );
+    add(settings.tag_weight, "for each tag roommates share");
+    add(
+        settings.theme_weight,
+        "for each tag of a person that is one of their room's themes",
+    );
+    add(
+        settings.rating_weight,
+        "for each unit of weights roommates give each other",
+    );
+    add(
+        -settings.popularity_penalty,
+        "times the product of roommates' popularity",
+    );
+    add(
+        -settings.size_pref_penalty,
+        "for each place a room is from someone's size_pref, or for anyone alone who isn't single_ok",
+    );
+    if let Some(fairness) = &problem.fairness {
+        add(
+            fairness.newcomer_boost,
+            "for each newcomer sharing with someone they preferred",
+        );
+    }
+    if !problem.rules.is_empty() {
+        parts.push("minus the weight of each broken rule".to_string());
+    }
+    parts
+}
+
+/// Each term shown with the results that applies to `problem`, with what
+/// it means under its settings.
+pub fn entries(problem: &Problem) -> Vec<(&'static str, String)> {
+    let mut entries = vec![
+        (
+            "preferred",
+            "both listed each other as preferred; a preference that isn't returned only counts as accepted".to_string(),
+        ),
+        (
+            "accepted",
+            "neither listed the other as unpreferred, but they didn't both prefer each other".to_string(),
+        ),
+        (
+            "unpreferred",
+            "either listed the other as unpreferred, whatever the other listed".to_string(),
+        ),
+    ];
+    if problem.allow_triple.len() >= 3 {
+        entries.push((
+            "room of three",
+            "the third is one more matchup: unpreferred if they and either of the pair marked the other unpreferred, otherwise preferred if they and either of the pair preferred each other".to_string(),
+        ));
+    }
+    if !problem.current.is_empty() {
+        entries.push((
+            "kept",
+            "rooms whose pair already shares in --current".to_string(),
+        ));
+    }
+    if let Some(max_moves) = problem.max_moves {
+        entries.push((
+            "moved",
+            format!("people from --current who no longer share with their current roommate, at most {max_moves} by --max-moves"),
+        ));
+    }
+    if !problem.flexible.is_empty() {
+        entries.push((
+            "flexible placements",
+            "flexible people needed to fill a room with someone who isn't flexible".to_string(),
+        ));
+    }
+    if !problem.preferred_venues.is_empty() {
+        entries.push((
+            "preferred venues",
+            "people whose room is at their preferred_venue, of everyone with one".to_string(),
+        ));
+    }
+    entries.push((
+        "ranking",
+        match problem.settings.ranking {
+            Ranking::Preferred => {
+                "most preferred matchups, then most accepted, then the highest soft score"
+            }
+            Ranking::Avoids => {
+                "fewest unpreferred matchups, then most preferred, then the highest soft score"
+            }
+            Ranking::Score => "the highest soft score, then most preferred, then most accepted",
+        }
+        .to_string(),
+    ));
+    if let Some(cap) = problem.settings.max_unpreferred {
+        entries.push((
+            "max_unpreferred",
+            format!("solutions with more than {cap} unpreferred matchups are thrown away"),
+        ));
+    }
+    let parts = soft_score(problem);
+    entries.push((
+        "soft score",
+        if parts.is_empty() {
+            "always 0, as every soft weight is 0".to_string()
+        } else {
+            parts.join("; ")
+        },
+    ));
+    entries
+}
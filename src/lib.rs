@@ -0,0 +1,73 @@
+//! Matching people into rooms of two from their preferences.
+//!
+//! A [`solver::Problem`] is never changed by solving it, and is `Send` and
+//! `Sync`, so any number of solves over the same problem can run at once
+//! on different threads. The solvers keep no state of their own: all the
+//! randomness a solve uses comes from the [`rng::Streams`] passed to it.
+//!
+//! Every file the program reads or writes carries a `schema_version`: the
+//! config ([`migrate::CURRENT_SCHEMA_VERSION`]), saved solutions and
+//! history records ([`history::SCHEMA_VERSION`]) and the run manifest
+//! ([`manifest::SCHEMA_VERSION`]). A version only goes up when a change
+//! would break existing readers, and older configs and records are
+//! upgraded as they are read.
+
+pub mod analytics;
+#[cfg(feature = "signing")]
+pub mod attest;
+pub mod auto;
+pub mod bipartite;
+pub mod blossom;
+pub mod changes;
+pub mod checkpoint;
+pub mod collate;
+pub mod components;
+pub mod confidence;
+pub mod config;
+pub mod date;
+pub mod demo;
+#[cfg(feature = "email")]
+pub mod email;
+pub mod estimate;
+pub mod exact;
+pub mod explain;
+pub mod export;
+pub mod fairness;
+pub mod gen;
+pub mod history;
+pub mod houses;
+pub mod hungarian;
+pub mod index;
+pub mod interchange;
+pub mod labels;
+pub mod legend;
+pub mod logger;
+pub mod manifest;
+pub mod migrate;
+pub mod moves;
+pub mod names;
+pub mod objective;
+pub mod pairing;
+pub mod ratings;
+pub mod repl;
+pub mod reroll;
+pub mod respondents;
+pub mod rng;
+pub mod robustness;
+pub mod rules;
+pub mod scores;
+pub mod signs;
+pub mod sizes;
+pub mod snapshot;
+pub mod solver;
+pub mod space;
+pub mod storage;
+pub mod summary;
+pub mod survey;
+pub mod swap;
+pub mod target;
+pub mod top;
+pub mod tune;
+pub mod venues;
+pub mod veto;
+pub mod who;
@@ -1,8 +1,10 @@
 use anyhow::Result;
 use colored::Colorize;
 use std::{
+    cell::RefCell,
     fmt,
     io::{self, Write},
+    sync::atomic::{AtomicBool, Ordering},
     time::{Duration, Instant},
 };
 
@@ -10,6 +12,120 @@ pub struct Logger {
     start: Instant,
 }
 
+/// Whether info lines are left out, for `--fast`.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Leaves out info lines from now on; warnings are still printed.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// The parts of a run that `--profile` reports time for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Reading configs, vetoes, history and saved solutions.
+    Parsing,
+    /// Checking pins, partners, tuning and the solutions budget.
+    Validation,
+    /// Building solutions, with whichever solver.
+    Generation,
+    /// Searching on from a first solution, for `--target`.
+    LocalSearch,
+    /// Picking the best of the solutions generated.
+    Ranking,
+    /// Printing and writing the results.
+    Output,
+}
+
+impl Phase {
+    pub const ALL: [Self; 6] = [
+        Self::Parsing,
+        Self::Validation,
+        Self::Generation,
+        Self::LocalSearch,
+        Self::Ranking,
+        Self::Output,
+    ];
+
+    pub const fn repr(&self) -> &str {
+        match self {
+            Self::Parsing => "parsing",
+            Self::Validation => "validation",
+            Self::Generation => "generation",
+            Self::LocalSearch => "local search",
+            Self::Ranking => "ranking",
+            Self::Output => "output",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Profile {
+    /// The phases open on this thread, innermost last, and when time was
+    /// last charged to the innermost.
+    open: Vec<Phase>,
+    since: Option<Instant>,
+    totals: [Duration; Phase::ALL.len()],
+}
+
+impl Profile {
+    /// Charges the time since the last change to the innermost open phase.
+    fn charge(&mut self) {
+        let now = Instant::now();
+        if let (Some(phase), Some(since)) = (self.open.last(), self.since) {
+            self.totals[*phase as usize] += now - since;
+        }
+        self.since = Some(now);
+    }
+}
+
+thread_local! {
+    // Only the main thread's phases are reported, so solves running on
+    // other threads at the same time can't muddle them.
+    static PROFILE: RefCell<Profile> = RefCell::default();
+}
+
+/// Time spent in a phase, counted until it is dropped. Time in a phase
+/// opened inside another only counts towards the inner one.
+pub struct Scope(());
+
+pub fn scope(phase: Phase) -> Scope {
+    PROFILE.with_borrow_mut(|x| {
+        x.charge();
+        x.open.push(phase);
+    });
+    Scope(())
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        PROFILE.with_borrow_mut(|x| {
+            x.charge();
+            x.open.pop();
+        });
+    }
+}
+
+/// How long this thread has spent in each phase so far.
+pub fn profile() -> Vec<(Phase, Duration)> {
+    PROFILE.with_borrow(|x| {
+        Phase::ALL
+            .iter()
+            .map(|p| (*p, x.totals[*p as usize]))
+            .collect()
+    })
+}
+
+/// A duration in the same units as the times logged.
+pub fn format_duration(duration: Duration) -> String {
+    let (time, unit) = display_duration(duration);
+    format!("{time}{}", unit.repr())
+}
+
 pub enum TimeUnit {
     Nanoseconds,
     Microseconds,
@@ -56,13 +172,21 @@ fn display_duration(duration: Duration) -> (u128, TimeUnit) {
 
 impl Logger {
     pub fn info<T: fmt::Display>(text: T) -> Result<Self> {
-        print!("{} {}", " INFO ".yellow(), text,);
-        io::stdout().flush()?;
+        if !quiet() {
+            print!("{} {}", " INFO ".yellow(), text,);
+            io::stdout().flush()?;
+        }
         Ok(Self {
             start: Instant::now(),
         })
     }
+    pub fn warn<T: fmt::Display>(text: T) {
+        println!("{} {}", " WARN ".red(), text);
+    }
     pub fn end(self) {
+        if quiet() {
+            return;
+        }
         let elapsed = self.start.elapsed();
         let (elapsed, unit) = display_duration(elapsed);
         println!(
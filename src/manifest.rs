@@ -0,0 +1,30 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::{config, objective, rng};
+
+/// The schema version of manifests written by this build. It only goes
+/// up when a change would break existing readers; new fields may appear
+/// at any time.
+pub const SCHEMA_VERSION: i64 = 1;
+
+/// A record of how a run was set up, enough to reproduce its result.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Manifest {
+    /// [`SCHEMA_VERSION`] when the manifest was written.
+    pub schema_version: i64,
+    pub version: &'static str,
+    pub config: Vec<PathBuf>,
+    pub mode: config::Mode,
+    pub solver: config::Strategy,
+    pub preset: Option<config::Preset>,
+    pub objective: Option<objective::Objective>,
+    pub ranking: config::Ranking,
+    pub solutions: u64,
+    pub max_memory: u64,
+    pub seed: u64,
+    pub deterministic: bool,
+    pub rng: &'static str,
+    pub streams: Vec<rng::StreamInfo>,
+}
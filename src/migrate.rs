@@ -0,0 +1,71 @@
+use anyhow::{anyhow, bail, Result};
+use toml_edit::{value, Document, Item, Table};
+
+/// The schema version written by this build of the program.
+pub const CURRENT_SCHEMA_VERSION: i64 = 2;
+
+/// Returns the schema version declared in `[config]`, if any.
+pub fn schema_version(doc: &Document) -> Result<Option<i64>> {
+    doc.get("config")
+        .and_then(|x| x.get("schema_version"))
+        .map(|version| {
+            version
+                .as_integer()
+                .ok_or_else(|| anyhow!("schema_version must be an integer"))
+        })
+        .transpose()
+}
+
+/// Rewrites `doc` in place until it matches the current schema, returning
+/// the version it started at. Configs without a schema version are taken
+/// to be version 1 (the original flat layout). Comments and formatting are
+/// preserved.
+pub fn upgrade(doc: &mut Document) -> Result<i64> {
+    upgrade_from(doc, 1)
+}
+
+/// Like [`upgrade`], but takes configs without a schema version to be
+/// written in `default` instead.
+pub fn upgrade_from(doc: &mut Document, default: i64) -> Result<i64> {
+    let original = schema_version(doc)?.unwrap_or(default);
+    if original > CURRENT_SCHEMA_VERSION {
+        bail!(
+            "config uses schema version {original}, but this build only understands up to {CURRENT_SCHEMA_VERSION}"
+        );
+    }
+    let mut version = original;
+    while version < CURRENT_SCHEMA_VERSION {
+        match version {
+            1 => v1_to_v2(doc)?,
+            _ => unreachable!(),
+        }
+        version += 1;
+        doc["config"]["schema_version"] = value(version);
+    }
+    Ok(original)
+}
+
+/// Version 1 kept every person as a top-level table next to `[config]`,
+/// which left no room for other sections. Version 2 moves them under
+/// `[people]`.
+fn v1_to_v2(doc: &mut Document) -> Result<()> {
+    if doc.contains_key("people") {
+        bail!("a person named \"people\" cannot be migrated automatically, please rename them");
+    }
+    let names = doc
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| key != "config")
+        .collect::<Vec<_>>();
+
+    let mut people = Table::new();
+    people.set_implicit(true);
+    for name in names {
+        let item = doc
+            .remove(&name)
+            .ok_or_else(|| anyhow!("Person disappeared during migration"))?;
+        people.insert(&name, item);
+    }
+    doc.insert("people", Item::Table(people));
+    Ok(())
+}
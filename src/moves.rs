@@ -0,0 +1,84 @@
+//! A limit on how much the rooms change for `--max-moves`, for publishing
+//! them again mid-term: at most that many people from `--current` may end
+//! up without their current roommate. Rather than hope a random sample
+//! lands within the limit, each sample keeps most current rooms as they
+//! are and only breaks up a few, worse ones more often than better ones.
+
+use rand::seq::SliceRandom;
+use rand::Rng as _;
+
+use crate::rng::Rng;
+use crate::solver::{allowed, occupants, tier, Pairs, Problem, Solution, Tier};
+
+/// Everyone from `--current` who doesn't share with their current
+/// roommate in `solution`.
+pub fn moved<'a>(solution: &'a Solution, problem: &Problem) -> Vec<&'a String> {
+    occupants(solution, problem)
+        .into_iter()
+        .flat_map(|(_, people)| {
+            people
+                .iter()
+                .filter(|x| {
+                    problem
+                        .current
+                        .get(x.as_str())
+                        .is_some_and(|x| !people.contains(&x))
+                })
+                .copied()
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Whether `solution` moves no more people than `--max-moves` allows.
+pub fn within(solution: &Solution, problem: &Problem) -> bool {
+    problem
+        .max_moves
+        .is_none_or(|x| moved(solution, problem).len() <= x)
+}
+
+/// Splits `people` into current rooms to keep as they are and everyone
+/// left to pair up. Anyone whose current roommate isn't among `people`, or
+/// may no longer share with them, is left to pair up, which counts against
+/// `max_moves`; of the rest, a random number of rooms that `max_moves`
+/// allows are broken up, an unpreferred room four times and an accepted
+/// room twice as likely to be picked as a preferred one.
+pub fn keep(
+    people: &[String],
+    max_moves: usize,
+    problem: &Problem,
+    rng: &mut Rng,
+) -> (Pairs, Vec<String>) {
+    let mut rooms = vec![];
+    let mut forced = 0;
+    for (i, a) in people.iter().enumerate() {
+        let Some(b) = problem.current.get(a) else {
+            continue;
+        };
+        match people.iter().position(|x| x == b) {
+            Some(j) if allowed(a, b, problem) => {
+                if i < j {
+                    rooms.push((a.clone(), b.clone()));
+                }
+            }
+            _ => forced += 1,
+        }
+    }
+    let breaks = rng.gen_range(0..=(max_moves.saturating_sub(forced) / 2).min(rooms.len()));
+    let broken = rooms
+        .choose_multiple_weighted(rng, breaks, |(a, b)| match tier(a, b, problem) {
+            Tier::Preferred => 1.0,
+            Tier::Accepted => 2.0,
+            Tier::Unpreferred => 4.0,
+        })
+        .expect("every weight is positive")
+        .cloned()
+        .collect::<Vec<_>>();
+    rooms.retain(|x| !broken.contains(x));
+    let rest = people
+        .iter()
+        .filter(|x| !rooms.iter().any(|(a, b)| a == *x || b == *x))
+        .cloned()
+        .collect();
+    (rooms, rest)
+}
@@ -0,0 +1,143 @@
+//! Matching names typed by people against the names in the config.
+
+use clap::ValueEnum;
+
+/// The number of single-character edits needed to turn `a` into `b`.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, x) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(x != *y);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Finds the names that best match a loosely typed query: an exact match
+/// ignoring case if there is one, otherwise every name containing the
+/// query, otherwise every name within a few edits of it (fewer for short
+/// queries, where a couple of edits can reach almost anything).
+pub fn fuzzy_find<'a>(query: &str, names: &'a [String]) -> Vec<&'a String> {
+    let query = query.to_lowercase();
+    let exact = names
+        .iter()
+        .filter(|x| x.to_lowercase() == query)
+        .collect::<Vec<_>>();
+    if !exact.is_empty() {
+        return exact;
+    }
+    let containing = names
+        .iter()
+        .filter(|x| x.to_lowercase().contains(&query))
+        .collect::<Vec<_>>();
+    if !containing.is_empty() {
+        return containing;
+    }
+    let max_edits = (query.chars().count() / 3).min(2);
+    names
+        .iter()
+        .filter(|x| levenshtein(&x.to_lowercase(), &query) <= max_edits)
+        .collect()
+}
+
+/// How names read from an imported file are matched against the roster.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Matching {
+    /// Only the name exactly as on the roster
+    #[default]
+    Exact,
+    /// The same name in any case
+    CaseInsensitive,
+    /// The same words in any order and case, so "Doe, Jane" matches
+    /// "Jane Doe"
+    Tokens,
+    /// The closest name within --max-edits edits, ignoring case
+    Fuzzy,
+}
+
+/// A row of an imported file naming nobody on the roster, or more than
+/// one person.
+#[derive(Debug)]
+pub struct Unmatched {
+    pub line: usize,
+    pub name: String,
+    /// Everyone the name matched, when it matched more than one person.
+    pub ambiguous: Vec<String>,
+    /// Names close to it, when it matched nobody.
+    pub closest: Vec<String>,
+}
+
+/// The words of a name, lowercased and sorted, ignoring commas.
+fn tokens(name: &str) -> Vec<String> {
+    let mut tokens = name
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|x| !x.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+    tokens.sort();
+    tokens
+}
+
+/// Matches names from an imported file against a roster.
+pub struct Matcher<'a> {
+    pub roster: &'a [String],
+    pub matching: Matching,
+    /// How many edits [`Matching::Fuzzy`] allows.
+    pub max_edits: usize,
+}
+
+impl<'a> Matcher<'a> {
+    /// Everyone on the roster `name` matches: one person, nobody, or
+    /// several when it's ambiguous.
+    pub fn candidates(&self, name: &str) -> Vec<&'a String> {
+        let name = name.trim();
+        let lower = name.to_lowercase();
+        let roster = self.roster.iter();
+        match self.matching {
+            Matching::Exact => roster.filter(|x| *x == name).collect(),
+            Matching::CaseInsensitive => roster.filter(|x| x.to_lowercase() == lower).collect(),
+            Matching::Tokens => {
+                let wanted = tokens(name);
+                roster.filter(|x| tokens(x) == wanted).collect()
+            }
+            Matching::Fuzzy => {
+                let distances = roster
+                    .map(|x| (levenshtein(&x.to_lowercase(), &lower), x))
+                    .filter(|x| x.0 <= self.max_edits)
+                    .collect::<Vec<_>>();
+                let best = distances.iter().map(|x| x.0).min();
+                distances
+                    .into_iter()
+                    .filter(|x| Some(x.0) == best)
+                    .map(|x| x.1)
+                    .collect()
+            }
+        }
+    }
+
+    /// The person on the roster `name` on `line` matches, or why it
+    /// doesn't match exactly one.
+    pub fn find(&self, name: &str, line: usize) -> Result<&'a String, Unmatched> {
+        match self.candidates(name).as_slice() {
+            [person] => Ok(person),
+            candidates => Err(Unmatched {
+                line,
+                name: name.to_string(),
+                ambiguous: candidates.iter().map(|x| x.to_string()).collect(),
+                closest: if candidates.is_empty() {
+                    fuzzy_find(name.trim(), self.roster)
+                        .into_iter()
+                        .take(3)
+                        .cloned()
+                        .collect()
+                } else {
+                    vec![]
+                },
+            }),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Objectives for `--objective`, which replace the config's ranking and
+//! soft weights for one run, e.g. to compare the rooms each gives for the
+//! same config.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::{Preset, Ranking, Settings};
+
+/// A ranking and soft weights read from a file for `weighted:`. Anything
+/// left out keeps its value from the config.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Weights {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ranking: Option<Ranking>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_current_weight: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stale_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_link_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disappointment_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag_weight: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme_weight: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rating_weight: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub popularity_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_pref_penalty: Option<f64>,
+}
+
+impl Weights {
+    /// Replaces the ranking and soft weights in `settings` with the ones
+    /// given here.
+    pub fn apply(&self, settings: &mut Settings) {
+        if let Some(ranking) = self.ranking {
+            settings.ranking = ranking;
+        }
+        for (value, setting) in [
+            (self.keep_current_weight, &mut settings.keep_current_weight),
+            (self.stale_penalty, &mut settings.stale_penalty),
+            (self.no_link_penalty, &mut settings.no_link_penalty),
+            (
+                self.disappointment_penalty,
+                &mut settings.disappointment_penalty,
+            ),
+            (self.tag_weight, &mut settings.tag_weight),
+            (self.theme_weight, &mut settings.theme_weight),
+            (self.rating_weight, &mut settings.rating_weight),
+            (self.popularity_penalty, &mut settings.popularity_penalty),
+            (self.size_pref_penalty, &mut settings.size_pref_penalty),
+        ] {
+            if let Some(value) = value {
+                *setting = value;
+            }
+        }
+    }
+}
+
+/// What a run optimises for, in place of the config's ranking and weights.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Objective {
+    /// As in [`Preset::FairnessFirst`].
+    Fair,
+    /// As in [`Preset::MaximizeFirstChoices`].
+    MaxPreferred,
+    /// As in [`Preset::StabilityFirst`].
+    Stability,
+    /// The weights in `file`, as they were when the run started.
+    Weighted { file: PathBuf, weights: Weights },
+}
+
+impl Objective {
+    /// Parses "fair", "max-preferred", "stability" or "weighted:<file>",
+    /// reading the file straight away.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "fair" => Ok(Self::Fair),
+            "max-preferred" => Ok(Self::MaxPreferred),
+            "stability" => Ok(Self::Stability),
+            _ => {
+                let Some(file) = text.strip_prefix("weighted:") else {
+                    return Err(format!(
+                        "{text:?} isn't an objective: use fair, max-preferred, stability or weighted:<file>"
+                    ));
+                };
+                let contents = fs::read_to_string(file).map_err(|e| format!("{file}: {e}"))?;
+                let weights = toml::from_str(&contents).map_err(|e| format!("{file}: {e}"))?;
+                Ok(Self::Weighted {
+                    file: file.into(),
+                    weights,
+                })
+            }
+        }
+    }
+
+    /// Replaces the ranking and soft weights in `settings` with this
+    /// objective's.
+    pub fn apply(&self, settings: &mut Settings) {
+        match self {
+            Self::Fair => Preset::FairnessFirst.apply(settings),
+            Self::MaxPreferred => Preset::MaximizeFirstChoices.apply(settings),
+            Self::Stability => Preset::StabilityFirst.apply(settings),
+            Self::Weighted { weights, .. } => weights.apply(settings),
+        }
+    }
+}
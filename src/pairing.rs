@@ -0,0 +1,50 @@
+//! How often each pair of people shares a room across the distinct optimal
+//! solutions found: a pair that shares in all of them is forced by the
+//! preferences, while one that shares in a few is down to chance.
+
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::config::Ranking;
+use crate::solver::{best_solutions, Compact, Problem};
+use crate::top::canonical;
+
+/// How many distinct optimal solutions there are among `samples`, and in
+/// how many of them each pair (lower index first) shares a room.
+pub fn shares(samples: &[Compact], ranking: Ranking) -> (usize, HashMap<[u16; 2], usize>) {
+    let distinct = best_solutions(samples, ranking)
+        .into_iter()
+        .map(|x| canonical(&x.rooms))
+        .collect::<HashSet<_>>();
+    let mut counts = HashMap::new();
+    for rooms in &distinct {
+        for pair in rooms {
+            *counts.entry(*pair).or_default() += 1;
+        }
+    }
+    (distinct.len(), counts)
+}
+
+/// Writes the share of optimal solutions in which each pair shares a room
+/// as a CSV matrix, with a row and a column per person.
+pub fn write_matrix(
+    path: &Path,
+    problem: &Problem,
+    solutions: usize,
+    counts: &HashMap<[u16; 2], usize>,
+) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer
+        .write_record(std::iter::once("name").chain(problem.people.iter().map(String::as_str)))?;
+    let n = problem.people.len() as u16;
+    for a in 0..n {
+        let row = (0..n).map(|b| {
+            let count = counts.get(&[a.min(b), a.max(b)]).copied().unwrap_or(0);
+            format!("{:.3}", count as f64 / solutions as f64)
+        });
+        writer.write_record(std::iter::once(problem.people[usize::from(a)].clone()).chain(row))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,149 @@
+//! Converts a survey in which everyone rated their classmates from 1 to 5
+//! into a config.
+//!
+//! The survey is a wide CSV file: a header row naming everyone, then one
+//! row per respondent with their own name first and a rating (or nothing)
+//! under each classmate. Each rating becomes a weight, from -1 for a
+//! rating of 1 to 1 for a rating of 5, and ratings past the thresholds
+//! also list the classmate as preferred or unpreferred.
+
+use anyhow::{anyhow, bail, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+use toml_edit::{value, Array, Document, InlineTable, Item, Table};
+
+use crate::collate;
+use crate::migrate::CURRENT_SCHEMA_VERSION;
+use crate::names::{Matcher, Unmatched};
+
+/// Which ratings count as preferred and which as unpreferred.
+pub struct Thresholds {
+    /// Ratings of this or lower list the classmate as unpreferred.
+    pub avoid_at_most: u8,
+    /// Ratings of this or higher list the classmate as preferred.
+    pub prefer_at_least: u8,
+}
+
+/// Each respondent's ratings of their classmates, by name.
+type Ratings = BTreeMap<String, BTreeMap<String, u8>>;
+
+/// Reads the survey, with every name matched against the roster when
+/// there is one. Columns and rows whose name doesn't match exactly one
+/// person on it are left out and returned.
+fn read(path: &Path, roster: Option<&Matcher>) -> Result<(Ratings, Vec<Unmatched>)> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let mut unmatched = vec![];
+    let mut resolve = |name: &str, line: usize| match roster {
+        Some(roster) => roster
+            .find(name, line)
+            .cloned()
+            .map_err(|x| unmatched.push(x))
+            .ok(),
+        None => Some(name.to_string()),
+    };
+    let names = reader
+        .headers()?
+        .iter()
+        .skip(1)
+        .map(|x| resolve(x.trim(), 1))
+        .collect::<Vec<_>>();
+    let mut ratings = Ratings::new();
+    for name in names
+        .iter()
+        .flatten()
+        .chain(roster.map_or(&[][..], |x| x.roster))
+    {
+        ratings.entry(name.clone()).or_default();
+    }
+    for row in reader.records() {
+        let row = row.map_err(|e| anyhow!("{}: {e}", path.display()))?;
+        let line = row.position().map_or(0, |x| x.line());
+        let Some(respondent) = row.get(0).map(str::trim).filter(|x| !x.is_empty()) else {
+            continue;
+        };
+        let Some(respondent) = resolve(respondent, line as usize) else {
+            continue;
+        };
+        let respondent = respondent.as_str();
+        let given = ratings.entry(respondent.to_string()).or_default();
+        for (name, cell) in names.iter().zip(row.iter().skip(1)) {
+            let cell = cell.trim();
+            let Some(name) = name.as_ref().filter(|x| *x != respondent) else {
+                continue;
+            };
+            if cell.is_empty() {
+                continue;
+            }
+            let rating = cell
+                .parse::<u8>()
+                .ok()
+                .filter(|x| (1..=5).contains(x))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "{}:{line}: {respondent}'s rating of {name} is \"{cell}\", not a number from 1 to 5",
+                        path.display()
+                    )
+                })?;
+            given.insert(name.clone(), rating);
+        }
+    }
+    Ok((ratings, unmatched))
+}
+
+/// Builds a config from a ratings survey, with `rating_weight` set so the
+/// weights count towards the soft score. With a `roster`, everyone on it
+/// is listed and names in the survey are matched against it, returning
+/// the ones that don't match exactly one person.
+pub fn import(
+    path: &Path,
+    thresholds: &Thresholds,
+    roster: Option<&Matcher>,
+) -> Result<(Document, Vec<Unmatched>)> {
+    if thresholds.avoid_at_most >= thresholds.prefer_at_least {
+        bail!("--avoid-at-most must be below --prefer-at-least");
+    }
+    let (ratings, unmatched) = read(path, roster)?;
+
+    let mut doc = Document::new();
+    let mut config = Table::new();
+    config.insert("solutions", value(10_000));
+    config.insert("schema_version", value(CURRENT_SCHEMA_VERSION));
+    config.insert("rating_weight", value(1.0));
+    doc.insert("config", Item::Table(config));
+    let mut people = Table::new();
+    people.set_implicit(true);
+    let mut names = ratings.keys().collect::<Vec<_>>();
+    collate::sort(&mut names);
+    for name in names {
+        let mut given = ratings[name].iter().collect::<Vec<_>>();
+        given.sort_by(|a, b| collate::compare(a.0, b.0));
+        let list = |keep: &dyn Fn(u8) -> bool| {
+            given
+                .iter()
+                .filter(|(_, rating)| keep(**rating))
+                .map(|(name, _)| name.as_str())
+                .collect::<Array>()
+        };
+        let mut person = Table::new();
+        person.insert(
+            "preferred",
+            value(list(&|x| x >= thresholds.prefer_at_least)),
+        );
+        person.insert(
+            "unpreferred",
+            value(list(&|x| x <= thresholds.avoid_at_most)),
+        );
+        person.insert(
+            "weights",
+            value(
+                given
+                    .iter()
+                    .map(|(name, rating)| (name.as_str(), (f64::from(**rating) - 3.0) / 2.0))
+                    .collect::<InlineTable>(),
+            ),
+        );
+        people.insert(name, Item::Table(person));
+    }
+    doc.insert("people", Item::Table(people));
+    Ok((doc, unmatched))
+}
@@ -0,0 +1,187 @@
+//! Commands for `repl`, a session over one config read from stdin: solve,
+//! swap people, pin rooms to keep them when solving again, check the
+//! score and save, without loading the config again for each step.
+
+use anyhow::{anyhow, bail, Result};
+use std::path::PathBuf;
+
+use crate::config::{Mode, Strategy};
+use crate::history::Record;
+use crate::rng::Streams;
+use crate::solver::{self, Problem, Solution};
+use crate::{auto, bipartite, components, exact, reroll};
+
+/// Each command's usage and what it does, as `help` lists them.
+pub const HELP: [(&str, &str); 9] = [
+    ("solve", "solve again, keeping any pinned rooms"),
+    ("rooms", "list the rooms, numbered for pin"),
+    ("score", "show the counters and soft score of the rooms"),
+    ("swap A B", "swap two people's places"),
+    (
+        "pin N...",
+        "keep the rooms numbered N... when solving again",
+    ),
+    (
+        "unpin [N...]",
+        "stop keeping the rooms numbered N..., or any",
+    ),
+    ("save PATH", "save the rooms, as --save does"),
+    ("help", "list these commands"),
+    ("quit", "end the session"),
+];
+
+pub enum Command {
+    Solve,
+    Rooms,
+    Score,
+    Swap(String, String),
+    Pin(Vec<usize>),
+    /// Every pinned room when empty.
+    Unpin(Vec<usize>),
+    Save(PathBuf),
+    Help,
+    Quit,
+}
+
+/// Splits a line into words, keeping anything in double quotes together
+/// so that names with spaces can be given, e.g. `swap "Mary Ann" Bea`.
+fn words(line: &str) -> Result<Vec<String>> {
+    let mut words = vec![];
+    let mut word = None::<String>;
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                word.get_or_insert_with(String::new);
+            }
+            _ if c.is_whitespace() && !quoted => words.extend(word.take()),
+            _ => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quoted {
+        bail!("a quote isn't closed");
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+impl Command {
+    /// Parses one line, or gives `None` for a blank one.
+    pub fn parse(line: &str) -> Result<Option<Self>> {
+        let words = words(line)?;
+        let Some((command, args)) = words.split_first() else {
+            return Ok(None);
+        };
+        let numbers = || {
+            args.iter()
+                .map(|x| {
+                    x.parse()
+                        .map_err(|_| anyhow!("{x} isn't a room number; see rooms"))
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Some(match (command.as_str(), args) {
+            ("solve", []) => Self::Solve,
+            ("rooms", []) => Self::Rooms,
+            ("score", []) => Self::Score,
+            ("swap", [a, b]) => Self::Swap(a.clone(), b.clone()),
+            ("pin", [_, ..]) => Self::Pin(numbers()?),
+            ("unpin", _) => Self::Unpin(numbers()?),
+            ("save", [path]) => Self::Save(path.into()),
+            ("help", []) => Self::Help,
+            ("quit" | "exit", []) => Self::Quit,
+            _ => match HELP
+                .iter()
+                .find(|x| x.0.split(' ').next() == Some(command.as_str()))
+            {
+                Some((usage, _)) => bail!("usage: {usage}"),
+                None => bail!("there's no command {command:?}; type help for the commands"),
+            },
+        }))
+    }
+}
+
+/// What a session keeps between commands.
+pub struct Session {
+    pub problem: Problem,
+    /// The rooms as they stand, once solved.
+    pub record: Option<Record>,
+    /// Rooms to keep when solving again, numbered from 1 as listed.
+    pub pinned: Vec<usize>,
+    streams: Streams,
+    date: String,
+    solves: u64,
+}
+
+impl Session {
+    /// Starts a session over `problem`, with `solver = "auto"` settled
+    /// once up front and every solve drawing from `seed`.
+    pub fn new(mut problem: Problem, seed: u64, date: String) -> Self {
+        if problem.settings.solver == Strategy::Auto {
+            problem.settings.solver = auto::choose(&problem).strategy;
+        }
+        Self {
+            problem,
+            record: None,
+            pinned: vec![],
+            streams: Streams::new(seed),
+            date,
+            solves: 0,
+        }
+    }
+
+    /// The rooms as they stand, failing if nothing has been solved yet.
+    pub fn record(&self) -> Result<&Record> {
+        self.record
+            .as_ref()
+            .ok_or_else(|| anyhow!("nothing is solved yet; type solve first"))
+    }
+
+    /// The rooms as they stand, scored against the config.
+    pub fn solution(&self) -> Result<Solution> {
+        self.record()?.solution(&self.problem)
+    }
+
+    /// Solves again with randomness of its own, as `--reroll` with
+    /// `--pin-rooms` would, keeping the pinned rooms as they are.
+    pub fn solve(&mut self, max_memory: u64) -> Result<&Record> {
+        let mut problem = self.problem.clone();
+        let kept = match &self.record {
+            Some(record) => reroll::keep(record, &self.pinned, &mut problem)?,
+            None => vec![],
+        };
+        let mut streams = self.streams.fork("repl", self.solves);
+        self.solves += 1;
+        let solution = match (problem.settings.mode, problem.settings.solver) {
+            _ if problem.people.len() < 2 => Solution::from_rooms(vec![], &problem),
+            (Mode::Bipartite, _) => bipartite::solve(&problem)?,
+            (_, Strategy::Exact) => exact::solve(&problem)?,
+            (_, Strategy::Components) => {
+                let budget = solver::sample_budget(&problem, max_memory);
+                components::solve(&problem, budget, &mut streams, false)?
+            }
+            _ => solver::sample_solution(&problem, max_memory, &mut streams, None, None)?.0,
+        };
+        let solution = reroll::restore(solution, &kept, &problem);
+        solver::check_cap(&solution, &problem)?;
+        Ok(self.record.insert(Record::new(
+            &solution,
+            &self.problem,
+            self.date.clone(),
+            self.streams.seed,
+        )))
+    }
+
+    /// Pins the rooms numbered `numbers`, failing on any there isn't.
+    pub fn pin(&mut self, numbers: &[usize]) -> Result<()> {
+        let rooms = self.record()?.rooms.len();
+        if let Some(number) = numbers.iter().find(|x| !(1..=rooms).contains(*x)) {
+            bail!("there is no room {number}: the rooms are numbered 1 to {rooms}");
+        }
+        self.pinned.extend(numbers);
+        self.pinned.sort_unstable();
+        self.pinned.dedup();
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+//! Solving again around rooms kept from a saved solution, for
+//! `--reroll` with `--pin-rooms`: the kept rooms must share exactly as
+//! before, and everyone else is solved afresh.
+
+use anyhow::{bail, Result};
+
+use crate::history::Record;
+use crate::solver::{allowed, Problem, Solution};
+
+/// A room kept from the saved solution.
+pub struct Kept {
+    /// Where the room was in the saved rooms, from 0.
+    pub index: usize,
+    pub name: String,
+    pub pair: (String, String),
+}
+
+/// Fixes the people in the saved rooms numbered `numbers` (from 1, as
+/// listed) to share again. With named rooms they're also pinned to the
+/// same room.
+pub fn keep(record: &Record, numbers: &[usize], problem: &mut Problem) -> Result<Vec<Kept>> {
+    let mut numbers = numbers.to_vec();
+    numbers.sort_unstable();
+    numbers.dedup();
+    let mut kept = vec![];
+    for number in numbers {
+        let Some(room) = number.checked_sub(1).and_then(|i| record.rooms.get(i)) else {
+            bail!(
+                "there is no room {number}: the saved solution has rooms 1 to {}",
+                record.rooms.len()
+            );
+        };
+        let [a, b] = room.people.as_slice() else {
+            bail!(
+                "{} holds {} people, but only rooms of two can be pinned",
+                room.name,
+                room.people.len()
+            );
+        };
+        if let Some(person) = [a, b]
+            .into_iter()
+            .find(|x| !problem.constraints.contains_key(*x))
+        {
+            bail!("{person} is in the saved rooms, but not in the config");
+        }
+        if !allowed(a, b, problem) {
+            bail!("{a} and {b} in {} can't share any more without breaking a pin, partner, veto or role", room.name);
+        }
+        if !problem.rooms.is_empty() {
+            if !problem.rooms.contains(&room.name) {
+                bail!("{} is in the saved rooms, but not in the config", room.name);
+            }
+            for person in [a, b] {
+                if let Some(pin) = problem.pins.get(person).filter(|x| **x != room.name) {
+                    bail!(
+                        "{person} is pinned to {pin}, so {} can't be kept",
+                        room.name
+                    );
+                }
+                problem.pins.insert(person.clone(), room.name.clone());
+            }
+        }
+        problem.fixed.insert(a.clone(), b.clone());
+        problem.fixed.insert(b.clone(), a.clone());
+        kept.push(Kept {
+            index: number - 1,
+            name: room.name.clone(),
+            pair: (a.clone(), b.clone()),
+        });
+    }
+    Ok(kept)
+}
+
+/// Moves the kept rooms (as returned by [`keep`]) back to where they were, so without named rooms
+/// they keep their numbers. Named rooms are already placed by their pins.
+pub fn restore(solution: Solution, kept: &[Kept], problem: &Problem) -> Solution {
+    if !problem.rooms.is_empty() || kept.is_empty() {
+        return solution;
+    }
+    let is = |pair: &(String, String), (a, b): &(String, String)| {
+        (&pair.0, &pair.1) == (a, b) || (&pair.0, &pair.1) == (b, a)
+    };
+    let third = solution
+        .third
+        .map(|(i, person)| (solution.result[i].clone(), person));
+    let (mut found, mut pairs): (Vec<_>, Vec<_>) = solution
+        .result
+        .into_iter()
+        .partition(|x| kept.iter().any(|room| is(x, &room.pair)));
+    // Kept rooms are in order, so each goes in after any before it.
+    for room in kept {
+        let Some(i) = found.iter().position(|x| is(x, &room.pair)) else {
+            continue;
+        };
+        pairs.insert(room.index.min(pairs.len()), found.remove(i));
+    }
+    let third = third.map(|(pair, person)| {
+        let i = pairs.iter().position(|x| *x == pair).unwrap();
+        (i, person)
+    });
+    Solution::with_third(pairs, third, problem)
+}
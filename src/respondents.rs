@@ -0,0 +1,109 @@
+//! Solving with only the people who filled in the survey (see
+//! [`Problem::respondents`]), for `--only-respondents`, and deciding what
+//! happens to everyone else.
+
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use rand::seq::SliceRandom;
+
+use crate::rng::Streams;
+use crate::solver::{find_solutions, Problem, Solution};
+
+/// How many arrangements of the non-respondents to draw for
+/// [`Policy::AppendRandom`], one of which is picked at random.
+const RANDOM_TRIES: u64 = 1000;
+
+/// What to do with the people who didn't respond.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Policy {
+    /// Solve the respondents first, then put everyone else in the rooms
+    /// left at random. If an odd number responded, one non-respondent
+    /// joins them to even it out.
+    AppendRandom,
+    /// Leave them out of the rooms altogether.
+    Exclude,
+    /// Solve them as a group of their own with the same solver, never
+    /// sharing with a respondent.
+    FillSeparately,
+}
+
+/// Which bucket each person fell into.
+#[derive(Debug, Default)]
+pub struct Split {
+    pub respondents: Vec<String>,
+    /// People who didn't respond, but are pinned with or partnered to
+    /// someone who did, so are solved along with them.
+    pub brought: Vec<String>,
+    pub others: Vec<String>,
+}
+
+impl Split {
+    pub fn new(problem: &Problem) -> Self {
+        let mut split = Self::default();
+        for person in &problem.people {
+            let bucket = if problem.respondents.contains(person) {
+                &mut split.respondents
+            } else if problem
+                .fixed
+                .get(person)
+                .is_some_and(|x| problem.respondents.contains(x))
+            {
+                &mut split.brought
+            } else {
+                &mut split.others
+            };
+            bucket.push(person.clone());
+        }
+        split
+    }
+
+    /// Everyone solved on the respondents' side.
+    pub fn solved(&self) -> Vec<String> {
+        self.respondents
+            .iter()
+            .chain(&self.brought)
+            .cloned()
+            .collect()
+    }
+}
+
+/// One solution holding the rooms of both `first` and `second`, solved
+/// for separate groups of `problem`, rescored against all of it.
+pub fn combine(first: Solution, second: Solution, problem: &Problem) -> Result<Solution> {
+    let offset = first.result.len();
+    let third = match (first.third, second.third) {
+        (Some(_), Some(_)) => {
+            bail!("both groups need a room of three, but there can only be one")
+        }
+        (third, None) => third,
+        (None, Some((i, person))) => Some((i + offset, person)),
+    };
+    let mut result = first.result;
+    result.extend(second.result);
+    Ok(Solution::with_third(result, third, problem))
+}
+
+/// Rooms for everyone in `problem` at random, only avoiding what can't be
+/// broken: pins, partners, vetoes and roles.
+pub fn random_rooms(problem: &Problem, max_memory: u64, streams: &mut Streams) -> Result<Solution> {
+    if problem.people.is_empty() {
+        return Ok(Solution::from_rooms(vec![], problem));
+    }
+    let mut problem = problem.clone();
+    problem.settings.solutions = RANDOM_TRIES;
+    problem.tuning.sample = None;
+    let solutions = find_solutions(
+        &problem,
+        max_memory,
+        &mut streams.get("append-random", 0),
+        None,
+        None,
+    )?;
+    let Some(solution) = solutions.choose(&mut streams.get("append-random", 1)) else {
+        bail!(
+            "no rooms for {} were found without breaking a pin, partner, veto or role",
+            problem.people.join(", ")
+        );
+    };
+    Ok(solution.expand(&problem))
+}
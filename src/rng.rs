@@ -0,0 +1,61 @@
+//! Seeded random number streams.
+//!
+//! Every part of a run that needs randomness gets its own stream, derived
+//! from the run seed, the subsystem's name and a worker index:
+//!
+//! * the ChaCha8 key comes from `seed` via `SeedableRng::seed_from_u64`
+//! * the ChaCha stream id is the 64-bit FNV-1a hash of `"{subsystem}/{worker}"`
+//!
+//! Streams never share state, so the numbers one subsystem draws don't
+//! depend on how much another has drawn, or in which order workers run.
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+pub type Rng = ChaCha8Rng;
+
+/// Where a stream came from, as recorded in the run manifest.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct StreamInfo {
+    pub subsystem: String,
+    pub worker: u64,
+    pub stream: u64,
+}
+
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Hands out the streams for a run and remembers which ones were used.
+pub struct Streams {
+    pub seed: u64,
+    pub used: Vec<StreamInfo>,
+}
+
+impl Streams {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, used: vec![] }
+    }
+
+    pub fn get(&mut self, subsystem: &str, worker: u64) -> Rng {
+        let stream = fnv1a(&format!("{subsystem}/{worker}"));
+        self.used.push(StreamInfo {
+            subsystem: subsystem.to_string(),
+            worker,
+            stream,
+        });
+        let mut rng = Rng::seed_from_u64(self.seed);
+        rng.set_stream(stream);
+        rng
+    }
+
+    /// A fresh set of streams, seeded from the `subsystem`/`worker` stream,
+    /// for repeating a whole solve with different randomness.
+    pub fn fork(&mut self, subsystem: &str, worker: u64) -> Self {
+        Self::new(rand::RngCore::next_u64(&mut self.get(subsystem, worker)))
+    }
+}
@@ -0,0 +1,103 @@
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::collate;
+use crate::rng::Streams;
+use crate::solver::{best_indices, build_rooms, Problem, Solution};
+
+/// How much everyone else's rooms change when one person drops out.
+pub struct Impact {
+    pub name: String,
+    /// How many of the others end up with a different roommate, not
+    /// counting the dropped person's own roommate, who always does.
+    pub moved: usize,
+    pub preferred: usize,
+    pub accepted: usize,
+    pub unpreferred: usize,
+}
+
+fn roommates(result: &[(String, String)]) -> HashMap<&str, &str> {
+    result
+        .iter()
+        .flat_map(|(a, b)| [(a.as_str(), b.as_str()), (b.as_str(), a.as_str())])
+        .collect()
+}
+
+/// How many of `others` have a different roommate in `after` than in
+/// `before`, ignoring whoever shared with `name` before.
+fn count_moved(
+    others: &[String],
+    name: &str,
+    before: &HashMap<&str, &str>,
+    after: &HashMap<&str, &str>,
+) -> usize {
+    others
+        .iter()
+        .filter(|x| before.get(x.as_str()) != Some(&name))
+        .filter(|x| before.get(x.as_str()) != after.get(x.as_str()))
+        .count()
+}
+
+/// Re-solves without each person in turn, with `budget` samples each, and
+/// measures how far the result has to move from `baseline`: among the best
+/// rooms found, the ones closest to the baseline are used. The most
+/// disruptive dropouts come first, as the people whose presence the rest
+/// of the solution hinges on.
+pub fn leave_one_out(
+    problem: &Problem,
+    baseline: &Solution,
+    budget: u64,
+    streams: &mut Streams,
+    deterministic: bool,
+) -> Result<Vec<Impact>> {
+    let before = roommates(&baseline.result);
+    let mut impacts = vec![];
+    for (i, name) in problem.people.iter().enumerate() {
+        let others = problem
+            .people
+            .iter()
+            .filter(|x| *x != name)
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut candidates = vec![];
+        let mut leftovers = vec![];
+        let mut rng = (!deterministic).then(|| streams.get("leave-one-out", i as u64));
+        for _ in 0..budget {
+            let (pairs, leftover) = build_rooms(problem, &others, rng.as_mut())?;
+            candidates.push(Solution::from_rooms(pairs, problem));
+            leftovers.push(leftover.len());
+        }
+        // Whoever can't be placed any more counts as moved, so prefer
+        // solutions that leave the fewest people out.
+        let fewest = leftovers.iter().copied().min().unwrap_or(0);
+        let candidates = candidates
+            .into_iter()
+            .zip(leftovers)
+            .filter(|(_, leftover)| *leftover == fewest)
+            .map(|(x, _)| x)
+            .collect::<Vec<_>>();
+        let Some((moved, solution)) = best_indices(&candidates, problem.settings.ranking)
+            .into_iter()
+            .map(|i| {
+                let after = roommates(&candidates[i].result);
+                (count_moved(&others, name, &before, &after), &candidates[i])
+            })
+            .min_by_key(|(moved, _)| *moved)
+        else {
+            continue;
+        };
+        impacts.push(Impact {
+            name: name.clone(),
+            moved,
+            preferred: solution.preferred,
+            accepted: solution.accepted,
+            unpreferred: solution.unpreferred,
+        });
+    }
+    impacts.sort_by(|a, b| {
+        b.moved
+            .cmp(&a.moved)
+            .then_with(|| collate::compare(&a.name, &b.name))
+    });
+    Ok(impacts)
+}
@@ -0,0 +1,425 @@
+//! Rules relating people's rooms to each other, beyond who shares with
+//! whom. A rule is a condition such as
+//!
+//! ```text
+//! not adjacent(Ana, Bea)
+//! not same_group(roommate(Ana), roommate(Bea))
+//! together(Cal, Dee) or adjacent(Cal, Dee)
+//! ```
+//!
+//! built from these predicates, each taking two people:
+//!
+//! - `together(a, b)`: `a` and `b` share a room
+//! - `adjacent(a, b)`: `a` and `b` are in neighbouring rooms, in room order
+//! - `same_group(a, b)`: `a` and `b` have the same `group`
+//!
+//! combined with `not`, `and`, `or` and parentheses. A person is a name
+//! (quoted if it isn't a single word) or `roommate(person)`. Every rule
+//! that doesn't hold takes its weight off the soft score.
+
+use anyhow::{anyhow, bail, Result};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+use crate::solver::{compare, place, Compact, Problem, Solution};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Open,
+    Close,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Word(x) => write!(f, "`{x}`"),
+            Self::Open => f.write_str("`(`"),
+            Self::Close => f.write_str("`)`"),
+            Self::Comma => f.write_str("`,`"),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '(' | ')' | ',' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    _ => Token::Comma,
+                });
+            }
+            '"' => {
+                chars.next();
+                let mut word = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => word.push(c),
+                        None => bail!("unclosed quote"),
+                    }
+                }
+                tokens.push(Token::Word(word));
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "(),\"".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Someone a predicate is about.
+#[derive(Debug, Clone)]
+enum Term {
+    Person(String),
+    Roommate(Box<Term>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Predicate {
+    Together,
+    Adjacent,
+    SameGroup,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Test(Predicate, Term, Term),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+struct Parser {
+    tokens: Peekable<IntoIter<Token>>,
+}
+
+impl Parser {
+    fn expect(&mut self, token: Token) -> Result<()> {
+        match self.tokens.next() {
+            Some(x) if x == token => Ok(()),
+            Some(x) => bail!("expected {token}, found {x}"),
+            None => bail!("expected {token}, found the end of the rule"),
+        }
+    }
+
+    fn word(&mut self) -> Result<String> {
+        match self.tokens.next() {
+            Some(Token::Word(x)) => Ok(x),
+            Some(x) => bail!("expected a name, found {x}"),
+            None => bail!("expected a name, found the end of the rule"),
+        }
+    }
+
+    fn is_keyword(&mut self, keyword: &str) -> bool {
+        self.tokens.peek() == Some(&Token::Word(keyword.to_string()))
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.is_keyword("or") {
+            self.tokens.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        while self.is_keyword("and") {
+            self.tokens.next();
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.is_keyword("not") {
+            self.tokens.next();
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.tokens.peek() == Some(&Token::Open) {
+            self.tokens.next();
+            let expr = self.or()?;
+            self.expect(Token::Close)?;
+            return Ok(expr);
+        }
+        let predicate = match self.word()?.as_str() {
+            "together" => Predicate::Together,
+            "adjacent" => Predicate::Adjacent,
+            "same_group" => Predicate::SameGroup,
+            x => bail!("unknown predicate {x}"),
+        };
+        self.expect(Token::Open)?;
+        let a = self.term()?;
+        self.expect(Token::Comma)?;
+        let b = self.term()?;
+        self.expect(Token::Close)?;
+        Ok(Expr::Test(predicate, a, b))
+    }
+
+    fn term(&mut self) -> Result<Term> {
+        let word = self.word()?;
+        if word == "roommate" && self.tokens.peek() == Some(&Token::Open) {
+            self.tokens.next();
+            let term = self.term()?;
+            self.expect(Token::Close)?;
+            return Ok(Term::Roommate(Box::new(term)));
+        }
+        Ok(Term::Person(word))
+    }
+}
+
+/// Where everyone ended up in one solution.
+struct Placement<'a> {
+    room_of: HashMap<&'a str, usize>,
+    roommate: HashMap<&'a str, &'a str>,
+}
+
+impl Term {
+    fn people<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Self::Person(x) => names.push(x),
+            Self::Roommate(x) => x.people(names),
+        }
+    }
+
+    /// Who this term refers to, or None if they aren't placed.
+    fn resolve<'a>(&'a self, placement: &Placement<'a>) -> Option<&'a str> {
+        match self {
+            Self::Person(x) => placement.room_of.contains_key(x.as_str()).then_some(x),
+            Self::Roommate(x) => placement.roommate.get(x.resolve(placement)?).copied(),
+        }
+    }
+}
+
+impl Expr {
+    fn people<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Self::Test(_, a, b) => {
+                a.people(names);
+                b.people(names);
+            }
+            Self::Not(x) => x.people(names),
+            Self::And(a, b) | Self::Or(a, b) => {
+                a.people(names);
+                b.people(names);
+            }
+        }
+    }
+
+    /// Whether the rule holds, or None if it is about someone who isn't
+    /// placed (e.g. while solving one preference cluster at a time).
+    fn holds(&self, placement: &Placement, problem: &Problem) -> Option<bool> {
+        Some(match self {
+            Self::Test(predicate, a, b) => {
+                let (a, b) = (a.resolve(placement)?, b.resolve(placement)?);
+                let (room_a, room_b) = (placement.room_of[a], placement.room_of[b]);
+                match predicate {
+                    Predicate::Together => room_a == room_b,
+                    Predicate::Adjacent => room_a.abs_diff(room_b) == 1,
+                    Predicate::SameGroup => {
+                        problem.groups.contains_key(a)
+                            && problem.groups.get(a) == problem.groups.get(b)
+                    }
+                }
+            }
+            Self::Not(x) => !x.holds(placement, problem)?,
+            Self::And(a, b) => a.holds(placement, problem)? && b.holds(placement, problem)?,
+            Self::Or(a, b) => a.holds(placement, problem)? || b.holds(placement, problem)?,
+        })
+    }
+}
+
+/// A parsed `[[rules]]` entry.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub text: String,
+    pub weight: f64,
+    pub priority: i64,
+    /// What breaking the rule takes off the soft score: its weight, scaled
+    /// up by [`weigh`] so that its priority comes first.
+    cost: f64,
+    expr: Expr,
+}
+
+impl Rule {
+    /// Parses a rule, making sure everyone it mentions is in `people`.
+    pub fn parse(text: &str, weight: f64, priority: i64, people: &HashSet<&str>) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?.into_iter().peekable(),
+        };
+        let expr = parser
+            .or()
+            .and_then(|expr| match parser.tokens.next() {
+                Some(x) => Err(anyhow!("unexpected {x}")),
+                None => Ok(expr),
+            })
+            .map_err(|e| anyhow!("rule `{text}`: {e}"))?;
+        let mut names = vec![];
+        expr.people(&mut names);
+        if let Some(name) = names.iter().find(|x| !people.contains(*x)) {
+            bail!("rule `{text}` mentions {name}, who isn't in the config");
+        }
+        Ok(Self {
+            text: text.to_string(),
+            weight,
+            priority,
+            cost: weight,
+            expr,
+        })
+    }
+}
+
+fn placement<'a>(result: &'a [(String, String)], problem: &Problem) -> Placement<'a> {
+    let mut room_of = HashMap::new();
+    let mut roommate = HashMap::new();
+    for (i, (_, (a, b))) in place(result, problem).into_iter().enumerate() {
+        room_of.insert(a.as_str(), i);
+        room_of.insert(b.as_str(), i);
+        roommate.insert(a.as_str(), b.as_str());
+        roommate.insert(b.as_str(), a.as_str());
+    }
+    Placement { room_of, roommate }
+}
+
+/// The rules that don't hold for a set of rooms.
+pub fn broken<'a>(result: &[(String, String)], problem: &'a Problem) -> Vec<&'a Rule> {
+    if problem.rules.is_empty() {
+        return vec![];
+    }
+    let placement = placement(result, problem);
+    problem
+        .rules
+        .iter()
+        .filter(|x| x.expr.holds(&placement, problem) == Some(false))
+        .collect()
+}
+
+/// The total cost of the rules broken by a set of rooms.
+pub fn penalty(result: &[(String, String)], problem: &Problem) -> f64 {
+    broken(result, problem).iter().map(|x| x.cost).sum()
+}
+
+/// Scales each rule's cost so that breaking any one rule costs more than
+/// breaking every rule of a lower priority put together.
+pub fn weigh(rules: &mut [Rule]) {
+    let mut priorities = rules.iter().map(|x| x.priority).collect::<Vec<_>>();
+    priorities.sort();
+    priorities.dedup();
+    let mut below = 0.0;
+    for priority in priorities {
+        let lightest = rules
+            .iter()
+            .filter(|x| x.priority == priority)
+            .map(|x| x.weight)
+            .filter(|x| *x > 0.0)
+            .fold(f64::INFINITY, f64::min);
+        let scale = if lightest.is_finite() {
+            ((below + 1.0) / lightest).max(1.0)
+        } else {
+            1.0
+        };
+        for rule in rules.iter_mut().filter(|x| x.priority == priority) {
+            rule.cost = rule.weight * scale;
+            below += rule.cost.max(0.0);
+        }
+    }
+}
+
+/// Why a rule was broken in the chosen rooms, going by the best sampled
+/// solution that keeps it.
+pub enum Reason<'a> {
+    /// None of the sampled solutions keeps it.
+    NeverHeld,
+    /// Keeping it breaks these rules, which have at least its priority and
+    /// hold in the chosen rooms.
+    Breaks(Vec<&'a Rule>),
+    /// Keeping it makes the solution rank lower; each field is how much
+    /// higher it would be than in the chosen rooms.
+    Ranks {
+        preferred: i64,
+        accepted: i64,
+        unpreferred: i64,
+        score: f64,
+    },
+}
+
+/// The rules broken by the chosen rooms, each with the reason it was given
+/// up for, found among `samples`. Without samples there is no reason.
+pub fn explain<'a>(
+    chosen: &Solution,
+    samples: &[Compact],
+    problem: &'a Problem,
+) -> Vec<(&'a Rule, Option<Reason<'a>>)> {
+    let relaxed = broken(&chosen.result, problem);
+    if relaxed.is_empty() || samples.is_empty() {
+        return relaxed.into_iter().map(|x| (x, None)).collect();
+    }
+    let contains = |rules: &[&Rule], rule: &Rule| rules.iter().any(|x| std::ptr::eq(*x, rule));
+    let mut keeping = vec![None::<(&Compact, Vec<&Rule>)>; relaxed.len()];
+    for sample in samples {
+        let rooms = sample
+            .rooms
+            .iter()
+            .map(|x| x.map(|i| problem.people[usize::from(i)].clone()))
+            .map(|[a, b]| (a, b))
+            .collect::<Vec<_>>();
+        let broken = broken(&rooms, problem);
+        for (rule, best) in relaxed.iter().zip(&mut keeping) {
+            if contains(&broken, rule) {
+                continue;
+            }
+            let better = best
+                .as_ref()
+                .is_none_or(|(x, _)| compare(sample, *x, problem.settings.ranking).is_gt());
+            if better {
+                *best = Some((sample, broken.clone()));
+            }
+        }
+    }
+    relaxed
+        .iter()
+        .zip(keeping)
+        .map(|(rule, best)| {
+            let Some((sample, broken)) = best else {
+                return (*rule, Some(Reason::NeverHeld));
+            };
+            let breaks = broken
+                .into_iter()
+                .filter(|x| x.priority >= rule.priority && !contains(&relaxed, x))
+                .collect::<Vec<_>>();
+            let reason = if breaks.is_empty() {
+                Reason::Ranks {
+                    preferred: sample.preferred as i64 - chosen.preferred as i64,
+                    accepted: sample.accepted as i64 - chosen.accepted as i64,
+                    unpreferred: sample.unpreferred as i64 - chosen.unpreferred as i64,
+                    score: sample.score - chosen.score,
+                }
+            } else {
+                Reason::Breaks(breaks)
+            };
+            (*rule, Some(reason))
+        })
+        .collect()
+}
@@ -0,0 +1,107 @@
+//! A breakdown of every candidate solution's soft score into the part each
+//! weight contributes, for tuning the weights against real configs rather
+//! than by feel.
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::solver::{best_indices, breaks_reuse, place, score_parts, size_score, theme_bonus};
+use crate::solver::{Compact, Problem, ScoreParts, Solution};
+use crate::{config, rules};
+
+#[derive(Serialize)]
+struct Row {
+    candidate: usize,
+    /// Whether the candidate is one of the best under the ranking.
+    best: bool,
+    preferred: usize,
+    accepted: usize,
+    unpreferred: usize,
+    kept: usize,
+    keep_current: f64,
+    stale: f64,
+    no_link: f64,
+    disappointment: f64,
+    tags: f64,
+    ratings: f64,
+    popularity: f64,
+    newcomer: f64,
+    themes: f64,
+    room_sizes: f64,
+    rules: f64,
+    /// How many people the candidate puts against `room_reuse`.
+    reuse_breaks: usize,
+    score: f64,
+}
+
+impl Row {
+    fn new(candidate: usize, best: bool, solution: &Solution, problem: &Problem) -> Self {
+        let mut parts = ScoreParts::default();
+        for (a, b) in &solution.result {
+            parts.add(&score_parts(a, b, problem));
+        }
+        let reuse_breaks = if problem.settings.room_reuse == config::RoomReuse::Ignore {
+            0
+        } else {
+            place(&solution.result, problem)
+                .iter()
+                .map(|(room, (a, b))| {
+                    breaks_reuse(a, room, problem) as usize
+                        + breaks_reuse(b, room, problem) as usize
+                })
+                .sum()
+        };
+        Self {
+            candidate,
+            best,
+            preferred: solution.preferred,
+            accepted: solution.accepted,
+            unpreferred: solution.unpreferred,
+            kept: solution.kept,
+            keep_current: parts.keep_current,
+            stale: parts.stale,
+            no_link: parts.no_link,
+            disappointment: parts.disappointment,
+            tags: parts.tags,
+            ratings: parts.ratings,
+            popularity: parts.popularity,
+            newcomer: parts.newcomer,
+            themes: theme_bonus(&solution.result, problem),
+            room_sizes: size_score(&solution.result, solution.third.as_ref(), problem),
+            rules: -rules::penalty(&solution.result, problem),
+            reuse_breaks,
+            score: solution.score,
+        }
+    }
+}
+
+/// Fails early for a file that wouldn't be written as CSV.
+pub fn check_path(path: &Path) -> Result<()> {
+    if path
+        .extension()
+        .is_some_and(|x| !x.eq_ignore_ascii_case("csv"))
+    {
+        bail!("--debug-scores can only write CSV files (ending in .csv)");
+    }
+    Ok(())
+}
+
+/// Writes one row per candidate to a CSV file. Without sampled candidates
+/// (any solver but the sample solver), only the chosen solution is written.
+pub fn write(path: &Path, problem: &Problem, samples: &[Compact], chosen: &Solution) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    if samples.is_empty() {
+        writer.serialize(Row::new(0, true, chosen, problem))?;
+    } else {
+        let mut best = vec![false; samples.len()];
+        for i in best_indices(samples, problem.settings.ranking) {
+            best[i] = true;
+        }
+        for (i, sample) in samples.iter().enumerate() {
+            writer.serialize(Row::new(i, best[i], &sample.expand(problem), problem))?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,191 @@
+//! Door signs for `--door-signs`: a page per room with its name and its
+//! people's names in large type, as HTML to print from a browser or as a
+//! PDF, so nobody has to type the rooms up again to stick on the doors.
+//! Notes and medical flags are left off, as the signs are up for all to see.
+
+use clap::ValueEnum;
+
+use crate::veto::escape_html;
+
+/// What door signs are written as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Html,
+    Pdf,
+}
+
+/// One door: the room's name, its venue if it has one, and who's in it.
+pub struct Sign<'a> {
+    pub room: &'a str,
+    pub venue: Option<&'a str>,
+    pub people: &'a [&'a String],
+}
+
+pub fn write(signs: &[Sign], format: Format) -> Vec<u8> {
+    match format {
+        Format::Html => html(signs).into_bytes(),
+        Format::Pdf => pdf(signs),
+    }
+}
+
+fn html(signs: &[Sign]) -> String {
+    let mut body = String::new();
+    for sign in signs {
+        body.push_str(&format!(
+            "<section>\n<div class=\"room\">{}</div>\n",
+            escape_html(sign.room)
+        ));
+        if let Some(venue) = sign.venue {
+            body.push_str(&format!(
+                "<div class=\"venue\">{}</div>\n",
+                escape_html(venue)
+            ));
+        }
+        for person in sign.people {
+            body.push_str(&format!(
+                "<div class=\"name\">{}</div>\n",
+                escape_html(person)
+            ));
+        }
+        body.push_str("</section>\n");
+    }
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Door signs</title>\n<style>\n\
+         body {{ margin: 0; font-family: sans-serif; }}\n\
+         section {{ height: 100vh; display: flex; flex-direction: column; justify-content: center; \
+         align-items: center; text-align: center; break-after: page; }}\n\
+         section:last-child {{ break-after: auto; }}\n\
+         .room {{ font-size: 32pt; color: #555; }}\n\
+         .venue {{ font-size: 18pt; color: #777; }}\n\
+         .name {{ font-size: 60pt; font-weight: bold; margin-top: 0.3em; overflow-wrap: anywhere; }}\n\
+         </style></head>\n<body>\n{body}</body>\n</html>\n"
+    )
+}
+
+/// The widths of Helvetica-Bold's printable ASCII characters, from space
+/// on, in thousandths of the font size.
+const WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+/// A4, in points.
+const PAGE: (f64, f64) = (595.0, 842.0);
+const MARGIN: f64 = 40.0;
+
+/// `text` in the PDF's WinAnsi encoding, as far as Latin-1 goes, with
+/// anything else as "?".
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match u32::from(c) {
+            x @ (0x20..=0x7e | 0xa0..=0xff) => x as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// How wide `text` is at a font size of 1. Letters with accents are as
+/// wide as most letters without.
+fn width(text: &[u8]) -> f64 {
+    text.iter()
+        .map(|x| match x {
+            0x20..=0x7e => f64::from(WIDTHS[usize::from(x - 0x20)]),
+            _ => 611.0,
+        })
+        .sum::<f64>()
+        / 1000.0
+}
+
+/// A PDF string literal of `text`, escaped.
+fn literal(text: &[u8]) -> String {
+    let mut literal = String::from("(");
+    for x in text {
+        match x {
+            b'(' | b')' | b'\\' => literal.push_str(&format!("\\{}", char::from(*x))),
+            0x20..=0x7e => literal.push(char::from(*x)),
+            _ => literal.push_str(&format!("\\{x:03o}")),
+        }
+    }
+    literal + ")"
+}
+
+/// Drawing `text` centred across the page at height `y`, as large as
+/// `size` but no wider than the page between the margins.
+fn centred(text: &str, size: f64, y: f64, grey: f64) -> String {
+    let text = encode(text);
+    let size = size.min((PAGE.0 - 2.0 * MARGIN) / width(&text).max(f64::EPSILON));
+    let x = (PAGE.0 - width(&text) * size) / 2.0;
+    format!(
+        "BT /F1 {size:.1} Tf {grey} g {x:.1} {y:.1} Td {} Tj ET\n",
+        literal(&text)
+    )
+}
+
+fn page(sign: &Sign) -> String {
+    let mut content = centred(sign.room, 32.0, PAGE.1 - 120.0, 0.35);
+    if let Some(venue) = sign.venue {
+        content.push_str(&centred(venue, 18.0, PAGE.1 - 150.0, 0.45));
+    }
+    let line = 100.0;
+    let top = PAGE.1 / 2.0 + (sign.people.len() as f64 - 1.0) * line / 2.0;
+    for (i, person) in sign.people.iter().enumerate() {
+        content.push_str(&centred(person, 60.0, top - i as f64 * line, 0.0));
+    }
+    content
+}
+
+/// A PDF with a page per sign, in Helvetica-Bold so that no font has to
+/// be embedded.
+fn pdf(signs: &[Sign]) -> Vec<u8> {
+    // The catalog, the page tree and the font come first, then each
+    // page and its contents.
+    let kids = (0..signs.len())
+        .map(|i| format!("{} 0 R", 4 + 2 * i))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{kids}] /Count {} /MediaBox [0 0 {} {}] >>",
+            signs.len(),
+            PAGE.0,
+            PAGE.1
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_string(),
+    ];
+    for (i, sign) in signs.iter().enumerate() {
+        let content = page(sign);
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            5 + 2 * i
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{content}endstream",
+            content.len()
+        ));
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = vec![];
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n{object}\nendobj\n", i + 1).as_bytes());
+    }
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        pdf.extend(format!("{offset:010} 00000 n \n").as_bytes());
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        )
+        .as_bytes(),
+    );
+    pdf
+}
@@ -0,0 +1,370 @@
+//! Rooms of more than two, from `room_size` or a room's `size`. The rest
+//! of the solver works in pairs, so these rooms are sampled on their own:
+//! people take turns joining a room with space, one with someone they
+//! mutually prefer if there is one, otherwise one where nobody marked
+//! anyone unpreferred, otherwise any room they may be in. Every pair in a
+//! room counts as a matchup, so a room of four has six.
+
+use anyhow::{anyhow, bail, Result};
+use rand::seq::SliceRandom;
+use std::cmp::Ordering;
+
+use crate::collate;
+use crate::config::{Mode, RoomReuse, Strategy};
+use crate::history::{self, Record};
+use crate::rng::Rng;
+use crate::solver::{
+    compare, is_disappointed, keeps_current, room_notes, score_parts, size_misses, tier,
+    within_cap, Problem, Ranked, Tier,
+};
+
+/// Rooms of any size, scored like a [`crate::solver::Solution`].
+pub struct Grouping {
+    /// Each room that has anyone in it, and who, in room order.
+    pub rooms: Vec<(String, Vec<String>)>,
+    pub preferred: usize,
+    pub accepted: usize,
+    pub unpreferred: usize,
+    pub kept: usize,
+    pub score: f64,
+}
+
+impl Ranked for Grouping {
+    fn counters(&self) -> (usize, usize, usize, f64) {
+        (self.preferred, self.accepted, self.unpreferred, self.score)
+    }
+}
+
+/// Whether any room in `problem` holds more than two people.
+pub fn applies(problem: &Problem) -> bool {
+    problem.settings.room_size > 2 || problem.room_sizes.values().any(|x| *x > 2)
+}
+
+/// Fails on settings that only work with rooms of two.
+pub fn check(problem: &Problem) -> Result<()> {
+    let settings = &problem.settings;
+    let unsupported = [
+        (settings.mode == Mode::Bipartite, "mode = \"bipartite\""),
+        (settings.solver == Strategy::Exact, "solver = \"exact\""),
+        (
+            settings.solver == Strategy::Components,
+            "solver = \"components\"",
+        ),
+        (settings.reserve_rooms > 0, "reserve_rooms"),
+        (settings.room_reuse != RoomReuse::Ignore, "room_reuse"),
+        (!problem.allow_triple.is_empty(), "allow_triple"),
+        (!problem.rules.is_empty(), "[[rules]]"),
+        (!problem.room_venues.is_empty(), "[venues]"),
+        (
+            problem.fairness.as_ref().is_some_and(|x| x.cap().is_some()),
+            "[fairness] max_gap",
+        ),
+    ];
+    if let Some((_, name)) = unsupported.iter().find(|x| x.0) {
+        bail!("{name} only works with rooms of two, but room_size or a room's size is larger");
+    }
+    Ok(())
+}
+
+/// How many people `room` holds.
+fn size(room: &str, problem: &Problem) -> usize {
+    problem
+        .room_sizes
+        .get(room)
+        .copied()
+        .unwrap_or(problem.settings.room_size)
+}
+
+/// The rooms to fill and how many each holds. Without `[rooms]`, as many
+/// rooms of `room_size` as everyone needs, labelled by `room_names`.
+/// Otherwise any room someone is pinned to, then the rest in order until
+/// there's space for everyone, so later rooms are left empty.
+pub fn capacities(problem: &Problem) -> Vec<(String, usize)> {
+    let people = problem.people.len();
+    if problem.rooms.is_empty() {
+        let size = problem.settings.room_size;
+        return (0..people.div_ceil(size))
+            .map(|i| (problem.room_names.label(i), size))
+            .collect();
+    }
+    let pinned = |x: &String| problem.pins.values().any(|room| room == x);
+    let mut space = problem
+        .rooms
+        .iter()
+        .filter(|x| pinned(x))
+        .map(|x| size(x, problem))
+        .sum::<usize>();
+    problem
+        .rooms
+        .iter()
+        .filter(|x| {
+            if pinned(x) {
+                return true;
+            }
+            let needed = space < people;
+            if needed {
+                space += size(x, problem);
+            }
+            needed
+        })
+        .map(|x| (x.clone(), size(x, problem)))
+        .collect()
+}
+
+/// Whether `a` and `b` may be in the same room without breaking a veto or
+/// `separate_roles`. Pins and partners are kept by how rooms are filled.
+fn allowed(a: &str, b: &str, problem: &Problem) -> bool {
+    problem.vetoed.get(a).is_none_or(|x| !x.contains(b))
+        && problem.vetoed.get(b).is_none_or(|x| !x.contains(a))
+        && (!problem.settings.separate_roles || problem.roles.get(a) == problem.roles.get(b))
+}
+
+/// Every pair of people in a room.
+fn pairs(people: &[String]) -> impl Iterator<Item = (&String, &String)> {
+    people
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| people[i + 1..].iter().map(move |b| (a, b)))
+}
+
+/// The worst tier of any pair in a room, or accepted for someone alone.
+pub fn room_tier(people: &[String], problem: &Problem) -> Tier {
+    pairs(people)
+        .map(|(a, b)| tier(a, b, problem))
+        .max()
+        .unwrap_or(Tier::Accepted)
+}
+
+impl Grouping {
+    /// Scores a complete set of rooms: each pair's tier and soft score, as
+    /// for a room of two, except that someone only counts as disappointed
+    /// when they share with none of the people they listed as preferred,
+    /// tags count once per person against the room's themes, and each
+    /// person's `size_pref` counts against how full their room is.
+    pub fn new(rooms: Vec<(String, Vec<String>)>, problem: &Problem) -> Self {
+        let settings = &problem.settings;
+        let largest = capacities(problem)
+            .iter()
+            .map(|x| x.1)
+            .max()
+            .unwrap_or(settings.room_size);
+        let (mut preferred, mut accepted, mut unpreferred) = (0, 0, 0);
+        let mut kept = 0;
+        let mut score = 0.0;
+        for (room, people) in &rooms {
+            for (a, b) in pairs(people) {
+                match tier(a, b, problem) {
+                    Tier::Preferred => preferred += 1,
+                    Tier::Accepted => accepted += 1,
+                    Tier::Unpreferred => unpreferred += 1,
+                }
+                if keeps_current(a, b, problem) {
+                    kept += 1;
+                }
+                let parts = score_parts(a, b, problem);
+                score += parts.total() - parts.disappointment;
+            }
+            let themes = problem.themes.get(room).map_or(&[][..], Vec::as_slice);
+            for person in people {
+                if people
+                    .iter()
+                    .filter(|x| *x != person)
+                    .all(|x| is_disappointed(person, x, problem))
+                {
+                    score -= settings.disappointment_penalty;
+                }
+                let matches = problem
+                    .tags
+                    .get(person)
+                    .map_or(0, |x| x.iter().filter(|x| themes.contains(x)).count());
+                score += settings.theme_weight * matches as f64;
+                score -= settings.size_pref_penalty
+                    * size_misses(person, people.len(), largest, problem) as f64;
+            }
+        }
+        Self {
+            rooms,
+            preferred,
+            accepted,
+            unpreferred,
+            kept,
+            score,
+        }
+    }
+
+    /// How many flexible people were needed to fill a room with someone
+    /// who isn't flexible, as [`crate::solver::flexible_placements`].
+    pub fn flexible_placements(&self, problem: &Problem) -> usize {
+        self.rooms
+            .iter()
+            .map(|(_, people)| {
+                let flexible = people
+                    .iter()
+                    .filter(|x| problem.flexible.contains(*x))
+                    .count();
+                if flexible < people.len() {
+                    flexible
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
+
+    /// The rooms as saved by `--save` and `--history`.
+    pub fn record(&self, problem: &Problem, date: String, seed: u64) -> Record {
+        Record {
+            schema_version: history::SCHEMA_VERSION,
+            date,
+            seed,
+            preferred: self.preferred,
+            accepted: self.accepted,
+            unpreferred: self.unpreferred,
+            rooms: self
+                .rooms
+                .iter()
+                .map(|(name, people)| {
+                    let (medical, notes) = room_notes(&people.iter().collect::<Vec<_>>(), problem);
+                    history::Room {
+                        name: name.clone(),
+                        people: people.clone(),
+                        tier: room_tier(people, problem),
+                        medical,
+                        notes,
+                    }
+                })
+                .collect(),
+            ids: problem
+                .people
+                .iter()
+                .filter_map(|x| Some((x.clone(), problem.ids.get(x)?.clone())))
+                .collect(),
+        }
+    }
+}
+
+/// Fills `rooms` once, as described at the top of this file, or returns
+/// `None` if someone was left with no room they may join.
+///
+/// With an rng the turn order and every choice are random. Without one
+/// turns go in alphabetical order and everyone takes the first room that
+/// suits them.
+fn fill(
+    problem: &Problem,
+    rooms: &[(String, usize)],
+    mut rng: Option<&mut Rng>,
+) -> Option<Vec<Vec<String>>> {
+    let mut people = problem.people.clone();
+    match &mut rng {
+        Some(rng) => people.shuffle(rng),
+        // People are popped off the end, so sort in reverse.
+        None => people.sort_by(|a, b| collate::compare(b, a)),
+    }
+    // Pinned people go first, then newcomers, and flexible people last.
+    people.sort_by_key(|x| {
+        (
+            problem.pins.contains_key(x),
+            problem.newcomers.contains(x),
+            !problem.flexible.contains(x),
+        )
+    });
+
+    let mut filled = vec![vec![]; rooms.len()];
+    while let Some(person) = people.pop() {
+        // Partners join a room together.
+        let mut joining = vec![person];
+        if let Some(i) = people
+            .iter()
+            .position(|x| problem.partners.get(&joining[0]) == Some(x))
+        {
+            joining.push(people.remove(i));
+        }
+        let fits = |i: &usize| {
+            let (room, size) = &rooms[*i];
+            let occupants: &Vec<String> = &filled[*i];
+            occupants.len() + joining.len() <= *size
+                && joining.iter().all(|x| {
+                    problem.pins.get(x).is_none_or(|x| x == room)
+                        && problem.max_room_sizes.get(x).is_none_or(|x| x >= size)
+                        && occupants.iter().all(|y| allowed(x, y, problem))
+                })
+        };
+        let open = (0..rooms.len()).filter(fits).collect::<Vec<_>>();
+        let mutual = open
+            .iter()
+            .copied()
+            .filter(|i| {
+                filled[*i]
+                    .iter()
+                    .any(|x| tier(&joining[0], x, problem) == Tier::Preferred)
+            })
+            .collect::<Vec<_>>();
+        let accepted = open
+            .iter()
+            .copied()
+            .filter(|i| {
+                filled[*i].iter().all(|x| {
+                    joining
+                        .iter()
+                        .all(|y| tier(x, y, problem) != Tier::Unpreferred)
+                })
+            })
+            .collect::<Vec<_>>();
+        let options = [mutual, accepted, open]
+            .into_iter()
+            .find(|x| !x.is_empty())?;
+        let choice = match &mut rng {
+            Some(rng) => *options.choose(rng)?,
+            None => options[0],
+        };
+        filled[choice].extend(joining);
+    }
+    Some(filled)
+}
+
+/// Fills the rooms `solutions` times, or once without an rng, and returns
+/// the best result under `ranking` that keeps within `max_unpreferred`.
+pub fn solve(problem: &Problem, mut rng: Option<&mut Rng>) -> Result<Grouping> {
+    let rooms = capacities(problem);
+    let attempts = if rng.is_some() {
+        problem.settings.solutions.max(1)
+    } else {
+        1
+    };
+    let mut best: Option<Grouping> = None;
+    let mut stuck = 0;
+    for _ in 0..attempts {
+        let Some(filled) = fill(problem, &rooms, rng.as_deref_mut()) else {
+            stuck += 1;
+            continue;
+        };
+        let grouping = Grouping::new(
+            rooms
+                .iter()
+                .map(|x| x.0.clone())
+                .zip(filled)
+                .filter(|x| !x.1.is_empty())
+                .collect(),
+            problem,
+        );
+        if !within_cap(&grouping, problem) {
+            continue;
+        }
+        if best
+            .as_ref()
+            .is_none_or(|x| compare(&grouping, x, problem.settings.ranking) == Ordering::Greater)
+        {
+            best = Some(grouping);
+        }
+    }
+    best.ok_or_else(|| {
+        if stuck == attempts {
+            anyhow!("couldn't fill the rooms without breaking a pin, partner, veto, forbidden pair, role or max_room_size; try a larger solutions budget or check the rooms have space for everyone")
+        } else {
+            anyhow!(
+                "every solution found has more than max_unpreferred = {} unpreferred matchups; try a larger solutions budget or ranking = \"avoids\"",
+                problem.settings.max_unpreferred.unwrap_or(0)
+            )
+        }
+    })
+}
@@ -0,0 +1,48 @@
+//! Frozen snapshots of a config, written by `freeze`: the merged config
+//! upgraded to the current schema, checked, and written out in one
+//! canonical form (people in name order, every setting spelled out)
+//! under a hash of its contents. A snapshot is itself a config file, and
+//! loading one fails if it has changed since it was frozen, so what gets
+//! solved is exactly what was checked.
+
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+use crate::config::Config;
+
+/// How the first line of a snapshot starts; the hash follows it.
+const HEADER: &str = "# room-matcher snapshot sha256:";
+
+fn hash(body: &str) -> String {
+    Sha256::digest(body.as_bytes())
+        .iter()
+        .map(|x| format!("{x:02x}"))
+        .collect()
+}
+
+/// Writes `config` out as a snapshot, returning its text and hash.
+pub fn freeze(config: &Config) -> Result<(String, String)> {
+    let body = toml::to_string(config)?;
+    let hash = hash(&body);
+    Ok((format!("{HEADER}{hash}\n{body}"), hash))
+}
+
+/// Whether `text` is a snapshot written by [`freeze`].
+pub fn is_snapshot(text: &str) -> bool {
+    text.starts_with(HEADER)
+}
+
+/// Makes sure the snapshot in `text`, read from `path`, hasn't changed
+/// since it was frozen, returning its hash.
+pub fn verify(text: &str, path: &Path) -> Result<String> {
+    let (first, body) = text.split_once('\n').unwrap_or((text, ""));
+    let expected = &first[HEADER.len()..];
+    if hash(body) != expected.trim() {
+        bail!(
+            "{} has been changed since it was frozen; freeze the config again",
+            path.display()
+        );
+    }
+    Ok(expected.trim().to_string())
+}
@@ -0,0 +1,1655 @@
+use anyhow::{anyhow, bail, Result};
+use colored::Colorize;
+use indexmap::{IndexMap, IndexSet};
+use rand::seq::SliceRandom;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::{fmt, mem};
+
+use crate::checkpoint::{Checkpoint, Checkpointer};
+use crate::rng::{Rng, Streams};
+use crate::{
+    collate, config, date, fairness, hungarian, labels, logger, moves, rules, sizes, venues,
+};
+
+pub type Constraints = IndexMap<String, (Vec<String>, Vec<String>)>;
+
+pub type Pairs = Vec<(String, String)>;
+
+#[derive(Clone)]
+pub struct Problem {
+    pub settings: config::Settings,
+    pub people: Vec<String>,
+    pub constraints: Constraints,
+    /// Each person's roommate before this run, as given by `--current`.
+    pub current: IndexMap<String, String>,
+    /// From `--max-moves`: the most people in `current` who may end up
+    /// without their current roommate.
+    pub max_moves: Option<usize>,
+    /// The group each person belongs to, for bipartite mode.
+    pub groups: IndexMap<String, String>,
+    /// People whose preferences are older than `max_preference_age_days`.
+    pub stale: IndexSet<String>,
+    /// Named rooms from `[rooms]`, in the order they are filled.
+    pub rooms: Vec<String>,
+    /// The `size` of each named room that has one.
+    pub room_sizes: IndexMap<String, usize>,
+    /// Each person's `max_room_size`, for those that have one.
+    pub max_room_sizes: IndexMap<String, usize>,
+    /// Each person's `size_pref`, for those that have one.
+    pub size_prefs: IndexMap<String, config::SizePref>,
+    /// The room each pinned person must be placed in.
+    pub pins: IndexMap<String, String>,
+    /// The venue each room from `[venues]` is at.
+    pub room_venues: IndexMap<String, String>,
+    /// The venue each person must stay at, from their `venue` or the room
+    /// they're pinned to.
+    pub venues: IndexMap<String, String>,
+    /// Each person's `preferred_venue`, for those that have one.
+    pub preferred_venues: IndexMap<String, String>,
+    /// Each person's partner from `partner_of` or `[[pins]]`, recorded on
+    /// both sides.
+    pub partners: IndexMap<String, String>,
+    /// Each person's `role`, for those that have one.
+    pub roles: IndexMap<String, String>,
+    /// People who filled in the survey: anyone with `preferred`,
+    /// `unpreferred` or `weights` entries, or a `submitted` date.
+    pub respondents: IndexSet<String>,
+    /// How many people listed each person as preferred, relative to the
+    /// most listed person, from 0 to 1. Nobody listed is left out.
+    pub popularity: IndexMap<String, f64>,
+    /// People pinned to the same room as someone else, or with a partner,
+    /// who must therefore share with exactly that person.
+    pub fixed: IndexMap<String, String>,
+    /// The named rooms each person (by [`Problem::identity`]) was in across
+    /// the history file, oldest first, for `room_reuse`.
+    pub past_rooms: IndexMap<String, Vec<String>>,
+    /// Rules from `[[rules]]`.
+    pub rules: Vec<rules::Rule>,
+    /// How rooms are labelled when there is no `[rooms]`.
+    pub room_names: labels::Scheme,
+    /// Each person's `tags`.
+    pub tags: IndexMap<String, Vec<String>>,
+    /// Each named room's `themes`.
+    pub themes: IndexMap<String, Vec<String>>,
+    /// Who each person may not share with, from their `forbidden` list
+    /// and `--vetoes`.
+    pub vetoed: IndexMap<String, IndexSet<String>>,
+    /// People with `allow_triple`, who may be one of three in a room.
+    pub allow_triple: IndexSet<String>,
+    /// People with `flexible`, who are placed last to fill gaps.
+    pub flexible: IndexSet<String>,
+    /// Each person's `weights`.
+    pub weights: IndexMap<String, BTreeMap<String, f64>>,
+    /// Each person's `id`, for those that have one.
+    pub ids: IndexMap<String, String>,
+    /// Each person's `house`, for those that have one.
+    pub houses: IndexMap<String, String>,
+    /// People with `medical` set.
+    pub medical: IndexSet<String>,
+    /// Each person's `notes`, for those that have them.
+    pub notes: IndexMap<String, String>,
+    /// Each person's `attributes`, for those that have any.
+    pub attributes: IndexMap<String, BTreeMap<String, String>>,
+    /// The cap and newcomer boost from `[fairness]`, see
+    /// [`crate::fairness`].
+    pub fairness: Option<config::Fairness>,
+    /// People new to the `--history` file, who get `newcomer_boost`.
+    pub newcomers: IndexSet<String>,
+    /// Parameters for the solver strategies, from `[solver]`, to be checked
+    /// against the strategy in use with [`config::Tuning::check`].
+    pub tuning: config::Tuning,
+}
+
+impl Problem {
+    /// Who `name` is across runs: their `id`, or their name without one.
+    pub fn identity<'a>(&'a self, name: &'a str) -> &'a str {
+        self.ids.get(name).map_or(name, String::as_str)
+    }
+
+    /// Builds the problem for a parsed config, with `today` (in days, see
+    /// `date`) deciding which dated preferences apply and whose are stale.
+    pub fn from_config(mut config: config::Config, today: i64) -> Result<Self> {
+        let room_venues = config.take_venue_rooms()?;
+        {
+            let _scope = logger::scope(logger::Phase::Validation);
+            config.check_rooms()?;
+            config.check_venues(&room_venues)?;
+        }
+        let log = logger::Logger::info("Parsing constraints".truecolor(100, 100, 100))?;
+        let rooms = config.rooms.keys().cloned().collect();
+        let room_sizes = config
+            .rooms
+            .iter()
+            .filter_map(|(name, room)| Some((name.clone(), room.size?)))
+            .collect();
+        let themes = config
+            .rooms
+            .iter()
+            .filter(|(_, room)| !room.themes.is_empty())
+            .map(|(name, room)| (name.clone(), room.themes.clone()))
+            .collect();
+        let mut people = vec![];
+        let mut tags = IndexMap::new();
+        let mut constraints = IndexMap::new();
+        let mut groups = IndexMap::new();
+        let mut pins = IndexMap::new();
+        let mut venues = IndexMap::new();
+        let mut preferred_venues = IndexMap::new();
+        let mut allow_triple = IndexSet::new();
+        let mut vetoed = IndexMap::<String, IndexSet<String>>::new();
+        let mut max_room_sizes = IndexMap::new();
+        let mut size_prefs = IndexMap::new();
+        let mut flexible = IndexSet::new();
+        let mut weights = IndexMap::new();
+        let mut ids = IndexMap::new();
+        let mut houses = IndexMap::new();
+        let mut medical = IndexSet::new();
+        let mut notes = IndexMap::new();
+        let mut partners = IndexMap::new();
+        let mut roles = IndexMap::new();
+        let mut respondents = IndexSet::new();
+        let mut attributes = IndexMap::new();
+        let mut stale = vec![];
+        let mut long = vec![];
+        for config::Pin { pair: [a, b] } in &config.pins {
+            partners.insert(a.clone(), b.clone());
+            partners.insert(b.clone(), a.clone());
+        }
+        for (name, person) in config.people {
+            people.push(name.clone());
+            if !person.preferred.is_empty()
+                || !person.unpreferred.is_empty()
+                || !person.weights.is_empty()
+                || person.submitted.is_some()
+            {
+                respondents.insert(name.clone());
+            }
+            if let Some(venue) = config::Config::venue_of(&person, &room_venues) {
+                venues.insert(name.clone(), venue.clone());
+            }
+            if let Some(group) = person.group {
+                groups.insert(name.clone(), group);
+            }
+            if let Some(venue) = person.preferred_venue {
+                preferred_venues.insert(name.clone(), venue);
+            }
+            if let Some(room) = person.room {
+                pins.insert(name.clone(), room);
+            }
+            if person.allow_triple {
+                allow_triple.insert(name.clone());
+            }
+            for other in person.forbidden {
+                vetoed
+                    .entry(other.clone())
+                    .or_default()
+                    .insert(name.clone());
+                vetoed.entry(name.clone()).or_default().insert(other);
+            }
+            if let Some(size) = person.max_room_size {
+                max_room_sizes.insert(name.clone(), size);
+            }
+            if let Some(pref) = person.size_pref {
+                size_prefs.insert(name.clone(), pref);
+            }
+            if person.flexible {
+                flexible.insert(name.clone());
+            }
+            if let Some(id) = person.id {
+                ids.insert(name.clone(), id);
+            }
+            if let Some(house) = person.house {
+                houses.insert(name.clone(), house);
+            }
+            if person.medical {
+                medical.insert(name.clone());
+            }
+            if let Some(note) = person.notes {
+                notes.insert(name.clone(), note);
+            }
+            if let Some(partner) = person.partner_of {
+                partners.insert(partner.clone(), name.clone());
+                partners.insert(name.clone(), partner);
+            }
+            if let Some(role) = person.role {
+                roles.insert(name.clone(), role);
+            }
+            if !person.attributes.is_empty() {
+                attributes.insert(name.clone(), person.attributes);
+            }
+            if !person.weights.is_empty() {
+                weights.insert(name.clone(), person.weights);
+            }
+            if !person.tags.is_empty() {
+                tags.insert(name.clone(), person.tags);
+            }
+            if let (Some(submitted), Some(max_age)) =
+                (&person.submitted, config.config.max_preference_age_days)
+            {
+                let age = today - date::from_toml(submitted)?;
+                if age > max_age as i64 {
+                    stale.push((name.clone(), age));
+                }
+            }
+            let resolve = |entries: Vec<config::Entry>| -> Result<Vec<String>> {
+                let mut names = vec![];
+                for entry in entries {
+                    if entry.applies_on(today)? {
+                        names.push(entry.name().to_string());
+                    }
+                }
+                Ok(names)
+            };
+            let mut preferred = resolve(person.preferred)?;
+            if let Some(max) = config.config.max_preferred.filter(|x| preferred.len() > *x) {
+                long.push((name.clone(), preferred.len()));
+                let rest = preferred.split_off(max);
+                if config.config.long_lists == config::LongLists::DownWeight {
+                    let weight = max as f64 / (max + rest.len()) as f64;
+                    let own = weights.entry(name.clone()).or_insert_with(BTreeMap::new);
+                    for other in rest {
+                        own.entry(other).or_insert(weight);
+                    }
+                }
+            }
+            constraints.insert(name, (preferred, resolve(person.unpreferred)?));
+        }
+        log.end();
+        collate::sort(&mut people);
+        constraints.sort_by(|a, _, b, _| collate::compare(a, b));
+        stale.sort_by(|a, b| collate::compare(&a.0, &b.0));
+
+        let names = people.iter().map(String::as_str).collect();
+        let mut rules = config
+            .rules
+            .iter()
+            .map(|x| rules::Rule::parse(&x.rule, x.weight, x.priority, &names))
+            .collect::<Result<Vec<_>>>()?;
+        rules::weigh(&mut rules);
+        let room_names = match &config.config.room_names {
+            Some(scheme) => labels::Scheme::parse(scheme)?,
+            None => labels::Scheme::default(),
+        };
+
+        let max = config.config.max_preferred.unwrap_or_default();
+        for (name, count) in &long {
+            logger::Logger::warn(format!(
+                "{} {} {} {}",
+                name.blue(),
+                "lists".truecolor(100, 100, 100),
+                count.to_string().truecolor(55, 80, 140),
+                match config.config.long_lists {
+                    config::LongLists::Truncate =>
+                        format!("people as preferred, only the first {max} count"),
+                    config::LongLists::DownWeight => format!(
+                        "people as preferred, those past the first {max} only count as weights"
+                    ),
+                }
+                .truecolor(100, 100, 100),
+            ));
+        }
+        for (name, age) in &stale {
+            logger::Logger::warn(format!(
+                "{} {} {}",
+                "Preferences of".truecolor(100, 100, 100),
+                name.blue(),
+                format!("are {age} days old").truecolor(100, 100, 100),
+            ));
+        }
+
+        if config.config.mode == config::Mode::Bipartite && !rules.is_empty() {
+            logger::Logger::warn(
+                "Bipartite mode only reports broken rules, it doesn't avoid breaking them"
+                    .truecolor(100, 100, 100),
+            );
+        }
+
+        if config.config.mode == config::Mode::Bipartite {
+            for name in &people {
+                let (preferred, unpreferred) = &constraints[name];
+                for other in preferred.iter().chain(unpreferred) {
+                    if groups.contains_key(name) && groups.get(name) == groups.get(other) {
+                        logger::Logger::warn(format!(
+                            "{} {} {} {}",
+                            name.blue(),
+                            "lists".truecolor(100, 100, 100),
+                            other.blue(),
+                            "from their own group, which bipartite mode ignores"
+                                .truecolor(100, 100, 100),
+                        ));
+                    }
+                }
+            }
+        }
+
+        let popularity = popularity(&constraints);
+        let mut problem = Problem {
+            settings: config.config,
+            people,
+            constraints,
+            current: IndexMap::new(),
+            max_moves: None,
+            groups,
+            stale: stale.into_iter().map(|(name, _)| name).collect(),
+            rooms,
+            room_sizes,
+            max_room_sizes,
+            size_prefs,
+            pins,
+            room_venues,
+            venues,
+            preferred_venues,
+            partners,
+            roles,
+            respondents,
+            popularity,
+            fixed: IndexMap::new(),
+            past_rooms: IndexMap::new(),
+            rules,
+            room_names,
+            tags,
+            themes,
+            vetoed,
+            allow_triple,
+            flexible,
+            weights,
+            ids,
+            houses,
+            medical,
+            notes,
+            attributes,
+            fairness: config.fairness,
+            newcomers: IndexSet::new(),
+            tuning: config.solver,
+        };
+        problem.fix_pairs();
+
+        let _scope = logger::scope(logger::Phase::Validation);
+        let minimum = problem
+            .settings
+            .min_feasible_partners
+            .min(problem.people.len().saturating_sub(1));
+        // Partners have nobody else to share with, by design.
+        for name in problem
+            .people
+            .iter()
+            .filter(|x| !problem.partners.contains_key(*x))
+        {
+            let count = feasible_partners(name, &problem);
+            if count < minimum {
+                logger::Logger::warn(format!(
+                    "{} {} {} {}",
+                    name.blue(),
+                    "can only share with".truecolor(100, 100, 100),
+                    count.to_string().truecolor(55, 80, 140),
+                    "people without an unpreferred room".truecolor(100, 100, 100),
+                ));
+            }
+        }
+        Ok(problem)
+    }
+
+    /// The same problem with only `people` in it. Anyone pinned with or
+    /// partnered to someone left out is free to share with anyone.
+    pub fn only(&self, people: &[String]) -> Self {
+        let keep = |x: &String| people.contains(x);
+        let mut problem = self.clone();
+        problem.people.retain(keep);
+        problem.constraints.retain(|x, _| keep(x));
+        problem.fixed.retain(|a, b| keep(a) && keep(b));
+        problem.partners.retain(|a, b| keep(a) && keep(b));
+        problem
+    }
+
+    /// Works out who must share with whom from the room pins and partners.
+    /// People pinned to a room of more than two may share with others.
+    pub fn fix_pairs(&mut self) {
+        let sized = sizes::applies(self);
+        for (a, room) in self.pins.iter().filter(|_| !sized) {
+            for (b, other) in &self.pins {
+                if a != b && room == other {
+                    self.fixed.insert(a.clone(), b.clone());
+                }
+            }
+        }
+        for (a, b) in &self.partners {
+            self.fixed.insert(a.clone(), b.clone());
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Solution {
+    pub result: Vec<(String, String)>,
+    /// With an odd number of people, the person joining the pair at this
+    /// position of `result` to make a room of three.
+    #[serde(default)]
+    pub third: Option<(usize, String)>,
+    pub preferred: usize,
+    pub accepted: usize,
+    pub unpreferred: usize,
+    pub kept: usize,
+    /// Weighted sum of the soft objective terms, used to choose between
+    /// solutions with equal counters.
+    pub score: f64,
+}
+
+/// How well a pair of roommates fits their preferences, best first.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Tier {
+    /// Both listed each other as preferred.
+    Preferred,
+    /// Neither listed the other as unpreferred.
+    Accepted,
+    Unpreferred,
+}
+
+impl fmt::Display for Tier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Preferred => "preferred",
+            Self::Accepted => "accepted",
+            Self::Unpreferred => "unpreferred",
+        })
+    }
+}
+
+pub fn tier(a: &str, b: &str, problem: &Problem) -> Tier {
+    let (a_preferred, a_unpreferred) = &problem.constraints[a];
+    let (b_preferred, b_unpreferred) = &problem.constraints[b];
+    if a_preferred.iter().any(|x| x == b) && b_preferred.iter().any(|x| x == a) {
+        Tier::Preferred
+    } else if a_unpreferred.iter().any(|x| x == b) || b_unpreferred.iter().any(|x| x == a) {
+        Tier::Unpreferred
+    } else {
+        Tier::Accepted
+    }
+}
+
+/// The soft objective contribution of putting `a` and `b` in a room together.
+pub fn pair_score(a: &str, b: &str, problem: &Problem) -> f64 {
+    score_parts(a, b, problem).total()
+}
+
+/// What each soft weight adds to (or, when negative, takes off) the soft
+/// score of one room.
+#[derive(Default)]
+pub struct ScoreParts {
+    pub keep_current: f64,
+    pub stale: f64,
+    pub no_link: f64,
+    pub disappointment: f64,
+    pub tags: f64,
+    pub ratings: f64,
+    pub popularity: f64,
+    pub newcomer: f64,
+}
+
+impl ScoreParts {
+    pub fn total(&self) -> f64 {
+        self.keep_current
+            + self.stale
+            + self.no_link
+            + self.disappointment
+            + self.tags
+            + self.ratings
+            + self.popularity
+            + self.newcomer
+    }
+
+    pub fn add(&mut self, other: &Self) {
+        self.keep_current += other.keep_current;
+        self.stale += other.stale;
+        self.no_link += other.no_link;
+        self.disappointment += other.disappointment;
+        self.tags += other.tags;
+        self.ratings += other.ratings;
+        self.popularity += other.popularity;
+        self.newcomer += other.newcomer;
+    }
+}
+
+/// The soft score of `a` and `b` sharing a room, weight by weight.
+pub fn score_parts(a: &str, b: &str, problem: &Problem) -> ScoreParts {
+    let settings = &problem.settings;
+    let mut parts = ScoreParts::default();
+    if keeps_current(a, b, problem) {
+        parts.keep_current = settings.keep_current_weight;
+    }
+    if (problem.stale.contains(a) || problem.stale.contains(b))
+        && tier(a, b, problem) == Tier::Preferred
+    {
+        parts.stale = -settings.stale_penalty;
+    }
+    if !has_link(a, b, problem) {
+        parts.no_link = -settings.no_link_penalty;
+    }
+    for (person, partner) in [(a, b), (b, a)] {
+        if is_disappointed(person, partner, problem) {
+            parts.disappointment -= settings.disappointment_penalty;
+        }
+    }
+    parts.tags = settings.tag_weight * shared_tags(a, b, problem) as f64;
+    if settings.rating_weight != 0.0 {
+        parts.ratings = settings.rating_weight * (weight(a, b, problem) + weight(b, a, problem));
+    }
+    if settings.popularity_penalty != 0.0 {
+        parts.popularity =
+            -settings.popularity_penalty * popularity_of(a, problem) * popularity_of(b, problem);
+    }
+    if let Some(fairness) = &problem.fairness {
+        for (person, partner) in [(a, b), (b, a)] {
+            if problem.newcomers.contains(person)
+                && problem.constraints[person].0.iter().any(|x| x == partner)
+            {
+                parts.newcomer += fairness.newcomer_boost;
+            }
+        }
+    }
+    parts
+}
+
+/// Whether `person` listed people as preferred, but not `partner`.
+pub fn is_disappointed(person: &str, partner: &str, problem: &Problem) -> bool {
+    let preferred = &problem.constraints[person].0;
+    !preferred.is_empty() && !preferred.iter().any(|x| x == partner)
+}
+
+fn tags<'a>(person: &str, problem: &'a Problem) -> &'a [String] {
+    problem.tags.get(person).map_or(&[], Vec::as_slice)
+}
+
+/// The weight `person` gives `other`, 0 if they didn't give one.
+pub fn weight(person: &str, other: &str, problem: &Problem) -> f64 {
+    problem
+        .weights
+        .get(person)
+        .and_then(|x| x.get(other))
+        .copied()
+        .unwrap_or(0.0)
+}
+
+/// `person`'s share of [`Problem::popularity`], 0 if nobody listed them.
+pub fn popularity_of(person: &str, problem: &Problem) -> f64 {
+    problem.popularity.get(person).copied().unwrap_or(0.0)
+}
+
+/// How many people list each person as preferred, relative to the most
+/// listed person.
+fn popularity(constraints: &IndexMap<String, (Vec<String>, Vec<String>)>) -> IndexMap<String, f64> {
+    let mut counts = IndexMap::<String, usize>::new();
+    for (preferred, _) in constraints.values() {
+        for name in preferred {
+            *counts.entry(name.clone()).or_default() += 1;
+        }
+    }
+    let most = counts.values().copied().max().unwrap_or(0);
+    counts
+        .into_iter()
+        .map(|(name, count)| (name, count as f64 / most as f64))
+        .collect()
+}
+
+/// How many tags `a` and `b` have in common.
+pub fn shared_tags(a: &str, b: &str, problem: &Problem) -> usize {
+    let theirs = tags(b, problem);
+    tags(a, problem)
+        .iter()
+        .filter(|x| theirs.contains(x))
+        .count()
+}
+
+/// How many of the tags of `a` and `b` are themes of `room`.
+pub fn theme_matches(a: &str, b: &str, room: &str, problem: &Problem) -> usize {
+    let Some(themes) = problem.themes.get(room) else {
+        return 0;
+    };
+    [a, b]
+        .iter()
+        .flat_map(|x| tags(x, problem))
+        .filter(|x| themes.contains(x))
+        .count()
+}
+
+/// Whether room themes can make a difference to the soft score.
+fn uses_themes(problem: &Problem) -> bool {
+    problem.settings.theme_weight != 0.0 && !problem.themes.is_empty()
+}
+
+/// The soft-score bonus for people's tags matching their room's themes.
+pub fn theme_bonus(result: &[(String, String)], problem: &Problem) -> f64 {
+    if !uses_themes(problem) {
+        return 0.0;
+    }
+    let matches = place(result, problem)
+        .iter()
+        .map(|(room, (a, b))| theme_matches(a, b, room, problem))
+        .sum::<usize>();
+    problem.settings.theme_weight * matches as f64
+}
+
+/// How many places a room of `size` is from the size `person` would like,
+/// when the largest room holds `largest`: each roommate past the first for
+/// "small", each place short of the largest room for "large", and one for
+/// being alone for anyone but "single_ok".
+pub fn size_misses(person: &str, size: usize, largest: usize, problem: &Problem) -> usize {
+    let alone = usize::from(size == 1);
+    match problem.size_prefs.get(person) {
+        Some(config::SizePref::Small) => size.saturating_sub(2) + alone,
+        Some(config::SizePref::Large) => largest.saturating_sub(size),
+        Some(config::SizePref::SingleOk) => 0,
+        None => alone,
+    }
+}
+
+/// What people's rooms being of a size they'd rather not be in takes off
+/// the soft score. Rooms hold two, apart from the one with the third, so
+/// only a room of three can make a difference.
+pub fn size_score(
+    result: &[(String, String)],
+    third: Option<&(usize, String)>,
+    problem: &Problem,
+) -> f64 {
+    if problem.settings.size_pref_penalty == 0.0 || problem.size_prefs.is_empty() {
+        return 0.0;
+    }
+    let largest = if third.is_some() { 3 } else { 2 };
+    let misses = result
+        .iter()
+        .enumerate()
+        .map(|(i, (a, b))| {
+            let third = third.filter(|x| x.0 == i).map(|x| &x.1);
+            let size = 2 + usize::from(third.is_some());
+            [a, b]
+                .into_iter()
+                .chain(third)
+                .map(|x| size_misses(x, size, largest, problem))
+                .sum::<usize>()
+        })
+        .sum::<usize>();
+    if misses == 0 {
+        return 0.0;
+    }
+    -problem.settings.size_pref_penalty * misses as f64
+}
+
+/// Whether at least one of `a` and `b` listed the other as preferred.
+pub fn has_link(a: &str, b: &str, problem: &Problem) -> bool {
+    problem.constraints[a].0.iter().any(|x| x == b)
+        || problem.constraints[b].0.iter().any(|x| x == a)
+}
+
+impl Solution {
+    /// Scores a complete set of rooms.
+    pub fn from_rooms(result: Vec<(String, String)>, problem: &Problem) -> Self {
+        Self::with_third(result, None, problem)
+    }
+
+    /// Scores a complete set of rooms, one of which may hold a third
+    /// person. The third person counts as one more matchup, of
+    /// [`third_tier`].
+    pub fn with_third(
+        result: Vec<(String, String)>,
+        third: Option<(usize, String)>,
+        problem: &Problem,
+    ) -> Self {
+        let tiers = result
+            .iter()
+            .map(|(a, b)| tier(a, b, problem))
+            .chain(
+                third
+                    .as_ref()
+                    .map(|(i, person)| third_tier(person, &result[*i], problem)),
+            )
+            .collect::<Vec<_>>();
+        let count = |t| tiers.iter().filter(|x| **x == t).count();
+        Self {
+            preferred: count(Tier::Preferred),
+            accepted: count(Tier::Accepted),
+            unpreferred: count(Tier::Unpreferred),
+            kept: result
+                .iter()
+                .filter(|(a, b)| keeps_current(a, b, problem))
+                .count(),
+            score: result
+                .iter()
+                .map(|(a, b)| pair_score(a, b, problem))
+                .sum::<f64>()
+                + theme_bonus(&result, problem)
+                + size_score(&result, third.as_ref(), problem)
+                - rules::penalty(&result, problem),
+            result,
+            third,
+        }
+    }
+}
+
+/// How well `person` fits in with a pair as the third in their room:
+/// unpreferred if they and either of the pair marked the other
+/// unpreferred, otherwise preferred if they and either of the pair
+/// preferred each other.
+pub fn third_tier(person: &str, (a, b): &(String, String), problem: &Problem) -> Tier {
+    let tiers = [tier(person, a, problem), tier(person, b, problem)];
+    if tiers.contains(&Tier::Unpreferred) {
+        Tier::Unpreferred
+    } else if tiers.contains(&Tier::Preferred) {
+        Tier::Preferred
+    } else {
+        Tier::Accepted
+    }
+}
+
+/// How well a room fits its people's preferences: the tier of its pair,
+/// or for a room of three, the worse of that and the third's tier.
+pub fn room_tier(people: &[&String], problem: &Problem) -> Tier {
+    let pair_tier = tier(people[0], people[1], problem);
+    let Some(third) = people.get(2) else {
+        return pair_tier;
+    };
+    pair_tier.max(third_tier(
+        third,
+        &(people[0].clone(), people[1].clone()),
+        problem,
+    ))
+}
+
+/// What duty staff should know about a room: whether anyone in it has
+/// `medical` set, and each occupant's `notes`, e.g. "Ana: nut allergy".
+pub fn room_notes(people: &[&String], problem: &Problem) -> (bool, Vec<String>) {
+    let medical = people.iter().any(|x| problem.medical.contains(*x));
+    let notes = people
+        .iter()
+        .filter_map(|x| Some(format!("{x}: {}", problem.notes.get(*x)?)))
+        .collect();
+    (medical, notes)
+}
+
+/// A room's medical flag and notes from [`room_notes`], to follow it
+/// wherever it's printed.
+pub fn notes_suffix(medical: bool, notes: &[String]) -> String {
+    let mut suffix = String::new();
+    if medical {
+        suffix += &format!(" {}", "[medical]".red());
+    }
+    if !notes.is_empty() {
+        suffix += &format!(" {}", notes.join("; ").truecolor(100, 100, 100));
+    }
+    suffix
+}
+
+/// Whether `person` may be the third in a room with `pair`. Someone who
+/// must stay at a venue only joins a pair who must stay there too, so
+/// the pair alone decides where the room goes.
+fn fits_third(person: &str, (a, b): &(String, String), problem: &Problem) -> bool {
+    [a, b].iter().all(|x| problem.allow_triple.contains(*x))
+        && allowed(person, a, problem)
+        && allowed(person, b, problem)
+        && problem
+            .venues
+            .get(person)
+            .is_none_or(|x| venues::venue(a, b, problem) == Some(x))
+}
+
+/// Picks the pair `person` joins as a third, among those where everyone
+/// allows a room of three: the one they fit in with best, at random (or
+/// the first, without an rng) among equals.
+pub fn join_third(
+    result: &[(String, String)],
+    person: &str,
+    problem: &Problem,
+    rng: Option<&mut Rng>,
+) -> Option<usize> {
+    if !problem.allow_triple.contains(person) || problem.pins.contains_key(person) {
+        return None;
+    }
+    let fits = (0..result.len())
+        .filter(|i| fits_third(person, &result[*i], problem))
+        .map(|i| (third_tier(person, &result[i], problem), i))
+        .collect::<Vec<_>>();
+    let best = fits.iter().map(|x| x.0).min()?;
+    let options = fits
+        .into_iter()
+        .filter(|x| x.0 == best)
+        .map(|x| x.1)
+        .collect::<Vec<_>>();
+    match rng {
+        Some(rng) => options.choose(rng).copied(),
+        None => options.first().copied(),
+    }
+}
+
+/// Makes the single person left over by [`build_rooms`], if any, the third
+/// in a room, failing if there's more than one or they can't join anyone.
+pub fn seat_leftover(
+    result: &[(String, String)],
+    leftover: &[String],
+    problem: &Problem,
+    rng: Option<&mut Rng>,
+) -> Result<Option<(usize, String)>> {
+    match leftover {
+        [] => Ok(None),
+        [person] => match join_third(result, person, problem, rng) {
+            Some(i) => Ok(Some((i, person.clone()))),
+            None => bail!("no one left that {person} may share with"),
+        },
+        [person, ..] => bail!("no one left that {person} may share with"),
+    }
+}
+
+/// How many flexible people were needed to fill a room with someone who
+/// isn't flexible.
+pub fn flexible_placements(solution: &Solution, problem: &Problem) -> usize {
+    let mut rooms = solution
+        .result
+        .iter()
+        .map(|(a, b)| vec![a, b])
+        .collect::<Vec<_>>();
+    if let Some((i, person)) = &solution.third {
+        rooms[*i].push(person);
+    }
+    rooms
+        .iter()
+        .map(|room| {
+            let flexible = room
+                .iter()
+                .filter(|x| problem.flexible.contains(**x))
+                .count();
+            if flexible < room.len() {
+                flexible
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+/// Each person in a room along with their roommates, e.g. "Ana and Bea"
+/// in a room of three.
+pub fn roommates<'a>(people: &'a [&'a String]) -> impl Iterator<Item = (&'a String, String)> {
+    people.iter().map(move |person| {
+        let others = people
+            .iter()
+            .filter(|x| *x != person)
+            .map(|x| x.as_str())
+            .collect::<Vec<_>>();
+        (*person, others.join(" and "))
+    })
+}
+
+/// Everyone in each room, in room order as given by [`place`], with the
+/// third person in a room of three last.
+pub fn occupants<'a>(solution: &'a Solution, problem: &Problem) -> Vec<(String, Vec<&'a String>)> {
+    let third = solution
+        .third
+        .as_ref()
+        .map(|(i, person)| (&solution.result[*i], person));
+    place(&solution.result, problem)
+        .into_iter()
+        .map(|(name, pair)| {
+            let mut people = vec![&pair.0, &pair.1];
+            if let Some((_, person)) = third.filter(|x| std::ptr::eq(x.0, pair)) {
+                people.push(person);
+            }
+            (name, people)
+        })
+        .collect()
+}
+
+/// Whether `a` and `b` may share a room at all without breaking a pin, a
+/// venue, a partner, a veto or `separate_roles`.
+pub fn allowed(a: &str, b: &str, problem: &Problem) -> bool {
+    let pins_agree = match (problem.pins.get(a), problem.pins.get(b)) {
+        (Some(x), Some(y)) => x == y,
+        _ => true,
+    };
+    let venues_agree = match (problem.venues.get(a), problem.venues.get(b)) {
+        (Some(x), Some(y)) => x == y,
+        _ => true,
+    };
+    pins_agree
+        && venues_agree
+        && problem.fixed.get(a).is_none_or(|x| x == b)
+        && problem.fixed.get(b).is_none_or(|x| x == a)
+        && problem.vetoed.get(a).is_none_or(|x| !x.contains(b))
+        && (!problem.settings.separate_roles || problem.roles.get(a) == problem.roles.get(b))
+}
+
+/// How many people `person` could share with without an unpreferred room
+/// or breaking a pin, partner, veto or role (and, in bipartite mode, without leaving their group).
+pub fn feasible_partners(person: &str, problem: &Problem) -> usize {
+    problem
+        .people
+        .iter()
+        .filter(|x| *x != person)
+        .filter(|x| tier(person, x, problem) != Tier::Unpreferred)
+        .filter(|x| allowed(person, x, problem))
+        .filter(|x| {
+            problem.settings.mode != config::Mode::Bipartite
+                || problem.groups.get(person) != problem.groups.get(*x)
+        })
+        .count()
+}
+
+/// Whether putting `person` in `room` goes against `room_reuse`.
+pub fn breaks_reuse(person: &str, room: &str, problem: &Problem) -> bool {
+    let past = problem.past_rooms.get(problem.identity(person));
+    match problem.settings.room_reuse {
+        config::RoomReuse::Ignore => false,
+        config::RoomReuse::Avoid => past.is_some_and(|x| x.iter().any(|x| x == room)),
+        config::RoomReuse::Keep => past.and_then(|x| x.last()).is_some_and(|x| x != room),
+    }
+}
+
+/// Decides which room each pair goes in and returns the rooms' labels
+/// alongside them, in room order. Pairs including a pinned person go to
+/// that room and the rest fill the remaining rooms, in order or, with
+/// `room_reuse`, so that as few people as possible go against it, and
+/// with `theme_weight`, so that as many tags as possible match their
+/// room's themes. With `[venues]`, pairs who must stay at a venue only go
+/// in its rooms, and then as few people as possible go against their
+/// `preferred_venue`, before room reuse. Without `[rooms]`, rooms are
+/// labelled by `room_names`.
+pub fn place<'a>(
+    result: &'a [(String, String)],
+    problem: &Problem,
+) -> Vec<(String, &'a (String, String))> {
+    if problem.rooms.is_empty() {
+        return result
+            .iter()
+            .enumerate()
+            .map(|(i, pair)| (problem.room_names.label(i), pair))
+            .collect();
+    }
+    let mut placed = vec![None; problem.rooms.len()];
+    let (pinned, free): (Vec<_>, Vec<_>) = result
+        .iter()
+        .partition(|(a, b)| problem.pins.contains_key(a) || problem.pins.contains_key(b));
+    for pair in pinned {
+        let room = problem
+            .pins
+            .get(&pair.0)
+            .or_else(|| problem.pins.get(&pair.1))
+            .unwrap();
+        let index = problem.rooms.iter().position(|x| x == room).unwrap();
+        placed[index] = Some(pair);
+    }
+    let open = (0..placed.len())
+        .filter(|i| placed[*i].is_none())
+        .collect::<Vec<_>>();
+    let reuse = problem.settings.room_reuse != config::RoomReuse::Ignore;
+    let spread = !problem.room_venues.is_empty();
+    let assignment = if reuse || uses_themes(problem) || spread {
+        let fit = |(a, b): &(String, String), room: &str| {
+            let breaks = if reuse {
+                breaks_reuse(a, room, problem) as usize + breaks_reuse(b, room, problem) as usize
+            } else {
+                0
+            };
+            (breaks, theme_matches(a, b, room, problem))
+        };
+        // Rooms at the wrong venue cost more than any other placement
+        // could, and a missed venue preference more than room reuse.
+        let venue_cost = |(a, b): &(String, String), room: &str| {
+            let wrong = venues::venue(a, b, problem)
+                .is_some_and(|x| problem.room_venues.get(room) != Some(x));
+            (wrong as usize, venues::misses(a, b, room, problem))
+        };
+        // Room reuse comes first: no number of theme matches is worth
+        // breaking it for one more person.
+        let most_matches = free
+            .iter()
+            .flat_map(|pair| open.iter().map(|i| fit(pair, &problem.rooms[*i]).1))
+            .max()
+            .unwrap_or(0);
+        let cost = free
+            .iter()
+            .map(|pair| {
+                open.iter()
+                    .map(|i| {
+                        let room = &problem.rooms[*i];
+                        let (breaks, matches) = fit(pair, room);
+                        let (wrong, misses) = venue_cost(pair, room);
+                        let cost = breaks * (most_matches + 1) + most_matches - matches;
+                        let cost = misses * 3 * (most_matches + 1) + cost;
+                        (wrong * 9 * (most_matches + 1) * (free.len() + 1) + cost) as f64
+                    })
+                    .collect()
+            })
+            .collect::<Vec<_>>();
+        hungarian::solve(&cost)
+    } else {
+        (0..free.len()).collect()
+    };
+    for (pair, slot) in free.into_iter().zip(assignment) {
+        placed[open[slot]] = Some(pair);
+    }
+    problem
+        .rooms
+        .iter()
+        .zip(placed)
+        .filter_map(|(name, pair)| Some((name.clone(), pair?)))
+        .collect()
+}
+
+/// Whether `a` and `b` shared a room before this run and neither of them
+/// listed the other as unpreferred.
+pub fn keeps_current(a: &str, b: &str, problem: &Problem) -> bool {
+    problem.current.get(a).is_some_and(|x| x == b)
+        && !problem.constraints[a].1.iter().any(|x| x == b)
+        && !problem.constraints[b].1.iter().any(|x| x == a)
+}
+
+/// Contracts each pair of people pinned to the same room (see
+/// [`Problem::fixed`]) into a finished room before a strategy runs, so
+/// that it is never broken up and the search only covers everyone else.
+/// Returns those rooms along with everyone left to pair up.
+pub fn contract(problem: &Problem) -> (Pairs, Vec<String>) {
+    let mut locked = vec![];
+    let mut free = vec![];
+    for person in &problem.people {
+        match problem.fixed.get(person) {
+            Some(other)
+                if problem.fixed.get_index_of(person) < problem.fixed.get_index_of(other) =>
+            {
+                locked.push((person.clone(), other.clone()));
+            }
+            Some(_) => {}
+            None => free.push(person.clone()),
+        }
+    }
+    (locked, free)
+}
+
+/// Picks one of `options` at random, or the alphabetically first when
+/// there is no rng (deterministic mode).
+fn pick<'a>(options: &'a [String], rng: &mut Option<&mut Rng>) -> Option<&'a String> {
+    match rng {
+        Some(rng) => options.choose(rng),
+        None => options.iter().min(),
+    }
+}
+
+/// Builds one solution greedily: people take turns, each sharing with a
+/// mutually preferred person if one is left, otherwise with someone neither
+/// side marked unpreferred, otherwise with anyone.
+///
+/// With an rng the turn order and every choice are random. Without one the
+/// run is deterministic: turns go in alphabetical order and each choice
+/// takes the alphabetically first option.
+pub fn solve_constraints(problem: &Problem, mut rng: Option<&mut Rng>) -> Result<Solution> {
+    let (mut result, free) = contract(problem);
+    let (pairs, leftover) = build_rooms(problem, &free, rng.as_deref_mut())?;
+    result.extend(pairs);
+    let third = seat_leftover(&result, &leftover, problem, rng)?;
+    Ok(Solution::with_third(result, third, problem))
+}
+
+/// Pairs up `people` as described in [`solve_constraints`], returning the
+/// rooms along with anyone left without a roommate they may share with.
+pub fn build_rooms(
+    problem: &Problem,
+    people: &[String],
+    rng: Option<&mut Rng>,
+) -> Result<(Pairs, Vec<String>)> {
+    build_rooms_within(problem, people, rng, usize::MAX)
+        .map(|x| x.expect("nothing is abandoned without a bound"))
+}
+
+/// As [`build_rooms`], but gives up with `None` as soon as more than
+/// `max_unpreferred` of the rooms so far are unpreferred, as the rest can
+/// only add to them.
+pub fn build_rooms_within(
+    problem: &Problem,
+    people: &[String],
+    mut rng: Option<&mut Rng>,
+    max_unpreferred: usize,
+) -> Result<Option<(Pairs, Vec<String>)>> {
+    let constraints = &problem.constraints;
+    let mut remaining_people = people.to_vec();
+    match &mut rng {
+        Some(rng) => remaining_people.shuffle(rng),
+        // People are popped off the end, so sort in reverse.
+        None => remaining_people.sort_by(|a, b| b.cmp(a)),
+    }
+    // Pinned people go first, while there is still someone unpinned left
+    // for each of them to share with, then newcomers, while their
+    // preferred roommates are still free. Flexible people go last.
+    remaining_people.sort_by_key(|x| {
+        (
+            problem.pins.contains_key(x),
+            problem.newcomers.contains(x),
+            !problem.flexible.contains(x),
+        )
+    });
+
+    let mut result = vec![];
+    let mut leftover = vec![];
+    let mut unpreferred = 0;
+
+    while !remaining_people.is_empty() {
+        let person = remaining_people
+            .pop()
+            .ok_or_else(|| anyhow!("List of remaining people is empty"))?
+            .clone();
+
+        let preferred_people = &constraints
+            .get(&person)
+            .ok_or_else(|| anyhow!("Person not in constraints"))?
+            .0;
+        let options = preferred_people
+            .iter()
+            .filter(|x| remaining_people.contains(x))
+            .filter(|x| constraints.get(*x).unwrap().0.contains(&person))
+            .filter(|x| allowed(&person, x, problem))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let unpreferred_people = &constraints
+            .get(&person)
+            .ok_or_else(|| anyhow!("Person not in constraints"))?
+            .1;
+        let (secondary_options, fillers): (Vec<_>, Vec<_>) = remaining_people
+            .iter()
+            .filter(|x| !unpreferred_people.contains(x))
+            .filter(|x| !constraints.get(*x).unwrap().1.contains(&person))
+            .filter(|x| allowed(&person, x, problem))
+            .cloned()
+            .partition(|x| !problem.flexible.contains(x));
+        // Flexible people are kept for whoever has nobody else left.
+        let secondary_options = if secondary_options.is_empty() {
+            fillers
+        } else {
+            secondary_options
+        };
+
+        if !options.is_empty() {
+            let choice =
+                pick(&options, &mut rng).ok_or_else(|| anyhow!("person not found in options"))?;
+            let index = remaining_people
+                .iter()
+                .position(|x| x == choice)
+                .ok_or_else(|| anyhow!("person not found in remaining_people"))?;
+            result.push((person, choice.clone()));
+            remaining_people.remove(index);
+        } else if !secondary_options.is_empty() {
+            let choice = pick(&secondary_options, &mut rng)
+                .ok_or_else(|| anyhow!("person not found in secondary_options"))?;
+            let index = remaining_people
+                .iter()
+                .position(|x| x == choice)
+                .ok_or_else(|| anyhow!("person not found in remaining_people"))?;
+            result.push((person, choice.clone()));
+            remaining_people.remove(index);
+        } else {
+            let fallback_options = remaining_people
+                .iter()
+                .filter(|x| allowed(&person, x, problem))
+                .cloned()
+                .collect::<Vec<_>>();
+            let Some(choice) = pick(&fallback_options, &mut rng) else {
+                leftover.push(person);
+                continue;
+            };
+            let index = remaining_people
+                .iter()
+                .position(|x| x == choice)
+                .ok_or_else(|| anyhow!("person not found in remaining_people"))?;
+            if tier(&person, choice, problem) == Tier::Unpreferred {
+                unpreferred += 1;
+                if unpreferred > max_unpreferred {
+                    return Ok(None);
+                }
+            }
+            result.push((person, choice.clone()));
+            remaining_people.remove(index);
+        }
+    }
+
+    Ok(Some((result, leftover)))
+}
+
+/// Roughly how many bytes a single stored solution takes up.
+pub fn solution_size(problem: &Problem) -> u64 {
+    let rooms = problem.people.len().div_ceil(2);
+    (mem::size_of::<Compact>() + rooms * mem::size_of::<[u16; 2]>()) as u64
+}
+
+/// The number of solutions to sample: the configured budget, reduced with
+/// a warning if storing that many would take more than `max_memory`.
+pub fn sample_budget(problem: &Problem, max_memory: u64) -> u64 {
+    let num_solutions = problem.settings.solutions;
+    let limit = (max_memory / solution_size(problem)).max(1);
+    if num_solutions <= limit {
+        return num_solutions;
+    }
+    logger::Logger::warn(format!(
+        "{} {} {} {} {}",
+        "Reducing solutions from".truecolor(100, 100, 100),
+        num_solutions.to_string().truecolor(55, 80, 140),
+        "to".truecolor(100, 100, 100),
+        limit.to_string().truecolor(55, 80, 140),
+        "to stay within --max-memory".truecolor(100, 100, 100),
+    ));
+    limit
+}
+
+/// Anything ranked like a solution.
+pub trait Ranked {
+    /// The preferred, accepted and unpreferred rooms, and the soft score.
+    fn counters(&self) -> (usize, usize, usize, f64);
+}
+
+impl Ranked for Solution {
+    fn counters(&self) -> (usize, usize, usize, f64) {
+        (self.preferred, self.accepted, self.unpreferred, self.score)
+    }
+}
+
+/// A solution as it is stored while sampling: each room as the positions
+/// of its two people in `problem.people`. Names are only filled in, with
+/// [`Compact::expand`], for the solutions that are actually used, which
+/// takes a fraction of the memory of keeping every [`Solution`].
+///
+/// Rooms are kept as pairs in room order rather than as each person's
+/// roommate, which is just as small but keeps the order that placement
+/// and rules depend on.
+#[derive(Debug, Clone)]
+pub struct Compact {
+    pub rooms: Vec<[u16; 2]>,
+    /// The position in `rooms` and the person of [`Solution::third`].
+    pub third: Option<[u16; 2]>,
+    pub preferred: u32,
+    pub accepted: u32,
+    pub unpreferred: u32,
+    pub score: f64,
+}
+
+impl Ranked for Compact {
+    fn counters(&self) -> (usize, usize, usize, f64) {
+        (
+            self.preferred as usize,
+            self.accepted as usize,
+            self.unpreferred as usize,
+            self.score,
+        )
+    }
+}
+
+/// Each person's position in `problem.people`, for [`Compact`].
+pub fn people_index(problem: &Problem) -> Result<HashMap<&str, u16>> {
+    if problem.people.len() > usize::from(u16::MAX) + 1 {
+        bail!(
+            "at most {} people are supported, but the config lists {}",
+            usize::from(u16::MAX) + 1,
+            problem.people.len()
+        );
+    }
+    Ok(problem
+        .people
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (x.as_str(), i as u16))
+        .collect())
+}
+
+impl Compact {
+    pub fn new(solution: &Solution, index: &HashMap<&str, u16>) -> Self {
+        Self {
+            rooms: solution
+                .result
+                .iter()
+                .map(|(a, b)| [index[a.as_str()], index[b.as_str()]])
+                .collect(),
+            third: solution
+                .third
+                .as_ref()
+                .map(|(i, person)| [*i as u16, index[person.as_str()]]),
+            preferred: solution.preferred as u32,
+            accepted: solution.accepted as u32,
+            unpreferred: solution.unpreferred as u32,
+            score: solution.score,
+        }
+    }
+
+    /// The full solution, with names.
+    pub fn expand(&self, problem: &Problem) -> Solution {
+        let name = |i: u16| problem.people[usize::from(i)].clone();
+        Solution::with_third(
+            self.rooms
+                .iter()
+                .map(|[a, b]| (name(*a), name(*b)))
+                .collect(),
+            self.third.map(|[i, person]| (usize::from(i), name(person))),
+            problem,
+        )
+    }
+}
+
+/// The counters `ranking` compares, most important first, each oriented so
+/// that higher is better.
+pub fn ranking_key(solution: &impl Ranked, ranking: config::Ranking) -> [f64; 3] {
+    let (preferred, accepted, unpreferred, score) = solution.counters();
+    let (preferred, accepted, unpreferred) =
+        (preferred as f64, accepted as f64, -(unpreferred as f64));
+    match ranking {
+        config::Ranking::Preferred => [preferred, accepted, score],
+        config::Ranking::Avoids => [unpreferred, preferred, score],
+        config::Ranking::Score => [score, preferred, accepted],
+    }
+}
+
+/// Whether a solution stays within `max_unpreferred`.
+pub fn within_cap(solution: &impl Ranked, problem: &Problem) -> bool {
+    problem
+        .settings
+        .max_unpreferred
+        .is_none_or(|x| solution.counters().2 <= x)
+}
+
+/// Fails if a solution breaks `max_unpreferred`, `[fairness]`,
+/// `--max-moves` or the rooms of a venue, e.g. because the search found
+/// nothing better, or puts together people who may never share.
+pub fn check_cap(solution: &Solution, problem: &Problem) -> Result<()> {
+    for (room, people) in occupants(solution, problem) {
+        for a in &people {
+            if let Some(b) = people
+                .iter()
+                .find(|b| problem.vetoed.get(*a).is_some_and(|x| x.contains(**b)))
+            {
+                bail!("the solution puts {a} and {b} together in {room}, but they may never share, from forbidden or --vetoes");
+            }
+        }
+    }
+    venues::check(&solution.result, problem)?;
+    if let Some(max_moves) = problem.max_moves {
+        let moved = moves::moved(solution, problem).len();
+        if moved > max_moves {
+            bail!("the best solution found moves {moved} people from --current, more than --max-moves = {max_moves}");
+        }
+    }
+    if let Some((attribute, max_gap)) = problem.fairness.as_ref().and_then(|x| x.cap()) {
+        let gap = fairness::gap(&fairness::audit(solution, attribute, problem));
+        if gap > max_gap {
+            bail!(
+                "the best solution found has a gap of {:.0} points in preferred rooms across {attribute}, more than max_gap = {max_gap}; try a larger solutions budget or the sample solver",
+                gap * 100.0,
+            );
+        }
+    }
+    if within_cap(solution, problem) {
+        return Ok(());
+    }
+    bail!(
+        "the best solution found has {} unpreferred rooms, more than max_unpreferred = {}; try a larger solutions budget or ranking = \"avoids\"",
+        solution.unpreferred,
+        problem.settings.max_unpreferred.unwrap_or(0)
+    )
+}
+
+/// Compares two solutions under `ranking`, better ones being greater.
+pub fn compare<T: Ranked>(a: &T, b: &T, ranking: config::Ranking) -> Ordering {
+    ranking_key(a, ranking)
+        .partial_cmp(&ranking_key(b, ranking))
+        .unwrap_or(Ordering::Equal)
+}
+
+/// The solutions tied for best under `ranking`.
+pub fn best_solutions<T: Ranked>(solutions: &[T], ranking: config::Ranking) -> Vec<&T> {
+    best_indices(solutions, ranking)
+        .into_iter()
+        .map(|i| &solutions[i])
+        .collect()
+}
+
+/// The positions of the solutions picked by [`best_solutions`].
+pub fn best_indices<T: Ranked>(solutions: &[T], ranking: config::Ranking) -> Vec<usize> {
+    let keys = solutions
+        .iter()
+        .map(|x| ranking_key(x, ranking))
+        .collect::<Vec<_>>();
+    // Arrays compare lexicographically, which is exactly the ranking order.
+    let best = keys
+        .iter()
+        .copied()
+        .reduce(|a, b| if b > a { b } else { a });
+    (0..solutions.len())
+        .filter(|i| Some(keys[*i]) == best)
+        .collect()
+}
+
+/// Generates random solutions, optionally continuing from a checkpoint and
+/// writing new ones as it goes. Solutions over `max_unpreferred` are
+/// dropped as soon as they're generated. With `patience` under
+/// `[solver.sample]`, generating stops early once that many solutions in a
+/// row haven't beaten the best so far. With `prune`, a sample is abandoned
+/// halfway as soon as it has more unpreferred rooms than it may keep: over
+/// `max_unpreferred`, or when ranking by `avoids`, over the best so far.
+pub fn find_solutions(
+    problem: &Problem,
+    max_memory: u64,
+    rng: &mut Rng,
+    resume: Option<Checkpoint>,
+    mut checkpointer: Option<Checkpointer>,
+) -> Result<Vec<Compact>> {
+    let index = people_index(problem)?;
+    let num_solutions = sample_budget(problem, max_memory);
+    let log = logger::Logger::info(format!(
+        "{} {} {}",
+        "Generating".truecolor(100, 100, 100),
+        num_solutions.to_string().truecolor(55, 80, 140),
+        "solutions".truecolor(100, 100, 100),
+    ))?;
+    let mut solutions = Vec::with_capacity(num_solutions as usize);
+    let mut done = 0;
+    if let Some(resume) = resume {
+        resume.check(problem)?;
+        rng.set_word_pos(resume.rng_word_pos);
+        done = resume.samples_done;
+        solutions.extend(resume.best.iter().map(|x| Compact::new(x, &index)));
+    }
+    // With an odd number of people, someone who allows a room of three is
+    // set aside to join a pair once everyone else is paired up.
+    let ranking = problem.settings.ranking;
+    let patience = problem.tuning.sample.as_ref().and_then(|x| x.patience);
+    let prune = problem.tuning.sample.as_ref().is_some_and(|x| x.prune);
+    let mut fewest_unpreferred = solutions.iter().map(|x| x.unpreferred as usize).min();
+    let mut pruned = 0;
+    let mut best = solutions
+        .iter()
+        .map(|x| ranking_key(x, ranking))
+        .reduce(|a, b| if b > a { b } else { a });
+    let mut since_best = 0;
+    let (locked, free) = contract(problem);
+    let locked_unpreferred = locked
+        .iter()
+        .filter(|(a, b)| tier(a, b, problem) == Tier::Unpreferred)
+        .count();
+    let thirds = if free.len() % 2 == 1 {
+        free.iter()
+            .filter(|x| problem.allow_triple.contains(*x) && !problem.pins.contains_key(*x))
+            .collect::<Vec<_>>()
+    } else {
+        vec![]
+    };
+    // Flexible people even out the numbers where they can.
+    let thirds = if thirds.iter().any(|x| problem.flexible.contains(*x)) {
+        thirds
+            .into_iter()
+            .filter(|x| problem.flexible.contains(*x))
+            .collect()
+    } else {
+        thirds
+    };
+    while done < num_solutions {
+        let third = thirds.choose(rng).copied();
+        let rest;
+        let people = match third {
+            Some(third) => {
+                rest = free
+                    .iter()
+                    .filter(|x| *x != third)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                &rest
+            }
+            None => &free,
+        };
+        // Rooms only ever get added, so a sample can be given up once it's
+        // over a cap, or worse than the best so far, in unpreferred rooms.
+        let mut bound = usize::MAX;
+        if prune {
+            let best = fewest_unpreferred.filter(|_| ranking == config::Ranking::Avoids);
+            for cap in [problem.settings.max_unpreferred, best]
+                .into_iter()
+                .flatten()
+            {
+                bound = bound.min(cap.saturating_sub(locked_unpreferred));
+            }
+        }
+        // With --max-moves, most current rooms are kept as they are and
+        // only everyone else is paired up.
+        let mut kept = vec![];
+        let moving;
+        let people = match problem.max_moves {
+            Some(max_moves) => {
+                (kept, moving) = moves::keep(people, max_moves, problem, rng);
+                &moving
+            }
+            None => people,
+        };
+        // Pins and vetoes can leave someone with no one they may share
+        // with, in which case the sample is dropped.
+        let rooms = build_rooms_within(problem, people, Some(rng), bound)?;
+        if rooms.is_none() {
+            pruned += 1;
+        }
+        let seated = rooms.map(|(pairs, leftover)| {
+            let mut result = locked.clone();
+            result.extend(kept);
+            result.extend(pairs);
+            let seated =
+                third.map(|x| join_third(&result, x, problem, Some(rng)).map(|i| (i, x.clone())));
+            (result, leftover, seated)
+        });
+        // A third who can't join any pair is left over too.
+        if let Some((result, _, seated)) =
+            seated.filter(|(_, leftover, seated)| leftover.is_empty() && *seated != Some(None))
+        {
+            let solution = Solution::with_third(result, seated.flatten(), problem);
+            if within_cap(&solution, problem)
+                && fairness::within(&solution, problem)
+                && moves::within(&solution, problem)
+                && venues::fits(&solution.result, problem)
+            {
+                let key = ranking_key(&solution, ranking);
+                if best.is_none_or(|x| key > x) {
+                    best = Some(key);
+                    since_best = 0;
+                }
+                fewest_unpreferred = fewest_unpreferred
+                    .map(|x| x.min(solution.unpreferred))
+                    .or(Some(solution.unpreferred));
+                solutions.push(Compact::new(&solution, &index));
+            }
+        }
+        done += 1;
+        since_best += 1;
+        if let Some(checkpointer) = &mut checkpointer {
+            if done % 1024 == 0 && checkpointer.due() {
+                checkpointer.write(&Checkpoint {
+                    people: problem.people.clone(),
+                    seed: checkpointer.seed,
+                    samples_done: done,
+                    rng_word_pos: rng.get_word_pos(),
+                    best: best_solutions(&solutions, problem.settings.ranking)
+                        .into_iter()
+                        .map(|x| x.expand(problem))
+                        .collect(),
+                })?;
+            }
+        }
+        if patience.is_some_and(|x| since_best >= x) {
+            break;
+        }
+    }
+    log.end();
+    if pruned > 0 {
+        logger::Logger::info(format!(
+            "{} {} {}",
+            "Abandoned".truecolor(100, 100, 100),
+            pruned.to_string().truecolor(55, 80, 140),
+            "solutions early, as they had too many unpreferred rooms".truecolor(100, 100, 100),
+        ))?
+        .end();
+    }
+    if done < num_solutions {
+        logger::Logger::info(format!(
+            "{} {} {} {} {}",
+            "Stopped after".truecolor(100, 100, 100),
+            done.to_string().truecolor(55, 80, 140),
+            "solutions, as the last".truecolor(100, 100, 100),
+            since_best.to_string().truecolor(55, 80, 140),
+            "found nothing better".truecolor(100, 100, 100),
+        ))?
+        .end();
+    }
+    Ok(solutions)
+}
+
+/// Generates the configured number of random solutions and picks one of
+/// the best.
+pub fn sample_solution(
+    problem: &Problem,
+    max_memory: u64,
+    streams: &mut Streams,
+    resume: Option<Checkpoint>,
+    checkpointer: Option<Checkpointer>,
+) -> Result<(Solution, Vec<Compact>)> {
+    let solutions = find_solutions(
+        problem,
+        max_memory,
+        &mut streams.get("sample", 0),
+        resume,
+        checkpointer,
+    )?;
+
+    if solutions.is_empty() {
+        match (problem.settings.max_unpreferred, problem.max_moves) {
+            (Some(cap), _) => bail!("no solution with at most {cap} unpreferred rooms was found; try a larger solutions budget"),
+            (None, _) if problem.fairness.as_ref().is_some_and(|x| x.cap().is_some()) => bail!("no solution within the [fairness] max_gap was found; try a larger solutions budget or a larger max_gap"),
+            (None, Some(max_moves)) => bail!("no solution moving at most {max_moves} people from --current was found; try a larger solutions budget or a larger --max-moves"),
+            (None, None) if problem.people.len() % 2 == 1 && problem.allow_triple.len() < 3 => bail!(
+                "{} people can't all be paired up, as that is an odd number; give at least three people allow_triple = true to allow one room of three",
+                problem.people.len()
+            ),
+            (None, None) => bail!("no solution pairing everyone up without breaking a pin, partner, veto, forbidden pair or role was found; try a larger solutions budget"),
+        }
+    }
+
+    let _scope = logger::scope(logger::Phase::Ranking);
+    let log = logger::Logger::info("Finding optimal solutions".truecolor(100, 100, 100))?;
+    let best_solutions = best_solutions(&solutions, problem.settings.ranking);
+    log.end();
+
+    let log = logger::Logger::info(format!(
+        "{} {} {}",
+        "Found".truecolor(100, 100, 100),
+        best_solutions.len().to_string().truecolor(55, 80, 140),
+        "optimal solutions".truecolor(100, 100, 100),
+    ))?;
+    log.end();
+
+    let log = logger::Logger::info("Selecting solution".truecolor(100, 100, 100))?;
+    let solution = best_solutions
+        .choose(&mut streams.get("select", 0))
+        .ok_or_else(|| anyhow!("No solutions found"))?;
+    log.end();
+    let solution = solution.expand(problem);
+    Ok((solution, solutions))
+}
@@ -0,0 +1,72 @@
+//! How big the search space is, to tell when the `solutions` budget is
+//! wildly out of proportion to it.
+//!
+//! The number of ways to pair up `n` people is `(n - 1)!!`, so it's worked
+//! with as a base-10 logarithm. The sample solver doesn't draw pairings
+//! evenly, but a small space is still covered after about `size * ln(size)`
+//! samples (as in collecting coupons), so asking for many times that is
+//! wasted time. A large space can never be covered, and there the budget
+//! only needs to give each person enough tries.
+
+use crate::config;
+use crate::solver::{contract, Problem};
+
+/// Budgets above this many times what it takes to see every pairing are
+/// reported as too many.
+const SURPLUS: f64 = 10.0;
+
+/// Budgets below this many solutions per person are reported as too few.
+const SAMPLES_PER_PERSON: u64 = 20;
+
+/// A `solutions` budget far from what the search space calls for.
+pub enum Mismatch {
+    /// The budget is many times what it takes to see every pairing.
+    TooMany {
+        /// The number of ways to pair everyone up.
+        size: u64,
+        suggested: u64,
+    },
+    /// The budget gives each person only a handful of tries.
+    TooFew { suggested: u64 },
+}
+
+/// The base-10 logarithm of the number of ways to put `people` people in
+/// rooms: pairs, and with an odd number and `triples`, one room of three.
+pub fn log10_size(people: usize, triples: bool) -> f64 {
+    let pairings = |n: usize| (1..n).step_by(2).map(|x| (x as f64).log10()).sum::<f64>();
+    if people.is_multiple_of(2) {
+        pairings(people)
+    } else if triples && people >= 3 {
+        // Who is the third, how the rest pair up and which pair they join.
+        (people as f64).log10() + pairings(people - 1) + (((people - 1) / 2) as f64).log10()
+    } else {
+        0.0
+    }
+}
+
+/// Compares the `solutions` budget with the search space of the sample
+/// solver, leaving out pairs pinned to the same room as they are never
+/// searched. Other strategies and modes aren't checked.
+pub fn check(problem: &Problem) -> Option<Mismatch> {
+    if problem.settings.mode != config::Mode::Pairs
+        || problem.settings.solver != config::Strategy::Sample
+    {
+        return None;
+    }
+    let (_, free) = contract(problem);
+    let budget = problem.settings.solutions;
+    let log10 = log10_size(free.len(), !problem.allow_triple.is_empty());
+    // Past 10^15 no budget gets anywhere near covering it.
+    if log10 < 15.0 {
+        let size = 10f64.powf(log10).round();
+        let enough = (size * size.ln()).ceil().max(1.0);
+        if budget as f64 > SURPLUS * enough {
+            return Some(Mismatch::TooMany {
+                size: size as u64,
+                suggested: enough as u64,
+            });
+        }
+    }
+    let floor = SAMPLES_PER_PERSON * free.len() as u64;
+    (budget < floor).then_some(Mismatch::TooFew { suggested: floor })
+}
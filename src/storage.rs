@@ -0,0 +1,183 @@
+//! Reading and writing the files that hold people's data: configs, saved
+//! solutions and history. With the `encryption` feature these can be
+//! encrypted with age, which is handled here so that everything else only
+//! ever sees plain text. Writes are atomic and take an advisory lock, so
+//! runs writing the same history file at once fail cleanly instead of
+//! corrupting it.
+
+#[cfg(feature = "encryption")]
+use anyhow::Context;
+use anyhow::{anyhow, bail, Result};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// How every age-encrypted file starts.
+const AGE_HEADER: &[u8] = b"age-encryption.org/";
+
+fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(AGE_HEADER)
+}
+
+/// Whether `path` is stored as plain text, so it can be read a piece at a
+/// time rather than decrypted whole.
+pub fn is_plain(path: &Path) -> Result<bool> {
+    let mut start = [0; AGE_HEADER.len()];
+    let mut file = File::open(path).map_err(|e| anyhow!("{}: {e}", path.display()))?;
+    let read = file.read(&mut start)?;
+    Ok(!is_encrypted(&start[..read]))
+}
+
+/// Where files are read from and written to, and the keys to do it with.
+#[derive(Default)]
+pub struct Storage {
+    #[cfg(feature = "encryption")]
+    recipient: Option<age::x25519::Recipient>,
+    #[cfg(feature = "encryption")]
+    identity: Option<age::x25519::Identity>,
+}
+
+/// The first line of a key file that isn't a comment.
+#[cfg(feature = "encryption")]
+fn read_key(path: &Path) -> Result<String> {
+    fs::read_to_string(path)
+        .with_context(|| format!("couldn't read {}", path.display()))?
+        .lines()
+        .map(str::trim)
+        .find(|x| !x.is_empty() && !x.starts_with('#'))
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("{} doesn't contain a key", path.display()))
+}
+
+impl Storage {
+    /// Encrypts what is written to `encrypt_to` (a file with an age public
+    /// key) and decrypts what is read with `decrypt_with` (an age identity
+    /// file, as written by `age-keygen`).
+    #[cfg(feature = "encryption")]
+    pub fn new(encrypt_to: Option<&Path>, decrypt_with: Option<&Path>) -> Result<Self> {
+        Ok(Self {
+            recipient: encrypt_to
+                .map(|path| {
+                    read_key(path)?
+                        .parse()
+                        .map_err(|e| anyhow!("{}: {e}", path.display()))
+                })
+                .transpose()?,
+            identity: decrypt_with
+                .map(|path| {
+                    read_key(path)?
+                        .parse()
+                        .map_err(|e| anyhow!("{}: {e}", path.display()))
+                })
+                .transpose()?,
+        })
+    }
+
+    fn encrypts(&self) -> bool {
+        #[cfg(feature = "encryption")]
+        return self.recipient.is_some();
+        #[cfg(not(feature = "encryption"))]
+        false
+    }
+
+    /// Reads a file as text, decrypting it if it is encrypted.
+    pub fn read(&self, path: &Path) -> Result<String> {
+        let bytes = fs::read(path).map_err(|e| anyhow!("{}: {e}", path.display()))?;
+        self.decode(path, bytes)
+    }
+
+    fn decode(&self, path: &Path, bytes: Vec<u8>) -> Result<String> {
+        if !is_encrypted(&bytes) {
+            return Ok(String::from_utf8(bytes)?);
+        }
+        #[cfg(feature = "encryption")]
+        {
+            let identity = self
+                .identity
+                .as_ref()
+                .ok_or_else(|| anyhow!("{} is encrypted; pass --decrypt-with", path.display()))?;
+            let plain = age::decrypt(identity, &bytes)
+                .map_err(|e| anyhow!("couldn't decrypt {}: {e}", path.display()))?;
+            Ok(String::from_utf8(plain)?)
+        }
+        #[cfg(not(feature = "encryption"))]
+        bail!(
+            "{} is encrypted, but this build can't decrypt it (build with --features encryption)",
+            path.display()
+        )
+    }
+
+    fn encode(&self, text: &str) -> Result<Vec<u8>> {
+        #[cfg(feature = "encryption")]
+        if let Some(recipient) = &self.recipient {
+            return age::encrypt(recipient, text.as_bytes())
+                .map_err(|e| anyhow!("couldn't encrypt: {e}"));
+        }
+        Ok(text.as_bytes().to_vec())
+    }
+
+    /// Writes text to a file, encrypting it with `--encrypt-to` if given.
+    pub fn write(&self, path: &Path, text: &str) -> Result<()> {
+        let _lock = lock(path)?;
+        replace(path, &self.encode(text)?)
+    }
+
+    /// Adds a line to the end of a file, creating it if needed. An
+    /// encrypted file stays encrypted, and a plain one is encrypted too if
+    /// `--encrypt-to` is given.
+    pub fn append(&self, path: &Path, line: &str) -> Result<()> {
+        let _lock = lock(path)?;
+        let text = match fs::read(path) {
+            Ok(bytes) if is_encrypted(&bytes) && !self.encrypts() => bail!(
+                "{} is encrypted; pass --encrypt-to to add to it",
+                path.display()
+            ),
+            Ok(bytes) => self.decode(path, bytes)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        replace(path, &self.encode(&format!("{text}{line}\n"))?)
+    }
+}
+
+/// `path` with `extension` added after its own, e.g. `history.jsonl.lock`.
+fn beside(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+/// Takes an advisory lock for writing `path`, held until the returned file
+/// is dropped. The lock is on a `.lock` file next to it, since `path`
+/// itself is replaced rather than written in place.
+fn lock(path: &Path) -> Result<File> {
+    let lock_path = beside(path, "lock");
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|e| anyhow!("{}: {e}", lock_path.display()))?;
+    match file.try_lock() {
+        Ok(()) => Ok(file),
+        Err(TryLockError::WouldBlock) => bail!(
+            "{} is being written by another process (it holds {}); try again once it has finished",
+            path.display(),
+            lock_path.display()
+        ),
+        Err(TryLockError::Error(e)) => Err(anyhow!("{}: {e}", lock_path.display())),
+    }
+}
+
+/// Replaces the contents of `path` in one step: they are written to a
+/// temporary file next to it, which is then renamed over it, so readers
+/// and crashes never see it half-written.
+fn replace(path: &Path, bytes: &[u8]) -> Result<()> {
+    let temp = beside(path, "tmp");
+    let mut file = File::create(&temp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&temp, path)?;
+    Ok(())
+}
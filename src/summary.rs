@@ -0,0 +1,25 @@
+use serde::Serialize;
+
+use crate::config;
+
+/// A one-line summary of a finished run, for wrappers that want its
+/// results without parsing the human-readable output.
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    pub preferred: usize,
+    pub accepted: usize,
+    pub unpreferred: usize,
+    pub kept: usize,
+    /// See [`crate::solver::flexible_placements`].
+    pub flexible_placements: usize,
+    pub score: f64,
+    pub duration_ms: u128,
+    pub mode: config::Mode,
+    pub solver: config::Strategy,
+    pub ranking: config::Ranking,
+    pub seed: u64,
+    pub deterministic: bool,
+    /// See [`crate::confidence::better_chance`]; only set by the sample
+    /// solver.
+    pub better_chance: Option<f64>,
+}
@@ -0,0 +1,285 @@
+//! Blank preference forms for the `survey` command, listing everyone so
+//! that the answers map straight onto `preferred` and `unpreferred` in the
+//! config.
+
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use std::collections::HashSet;
+
+use crate::veto::escape_html;
+
+/// What the form is written as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// A web page with a working form
+    Html,
+    /// A Word document, for printing or filling in by hand
+    Docx,
+    /// Markdown, e.g. for pasting into a shared document
+    Md,
+}
+
+/// How people give their answers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Style {
+    /// A row per person, with a box to tick for preferred and one for
+    /// unpreferred
+    Grid,
+    /// Blank lines to write names in, with everyone listed below
+    FreeText,
+}
+
+/// How many names the free-text form has room for.
+const PREFERRED_LINES: usize = 3;
+const UNPREFERRED_LINES: usize = 2;
+
+const TITLE: &str = "Room preferences";
+const NAME_PROMPT: &str = "Your name";
+const GRID_HELP: &str = "Tick the people you would like to share a room with under preferred, \
+     and anyone you would rather not share with under unpreferred. Leave everyone else blank.";
+const PREFERRED_PROMPT: &str = "Who would you like to share a room with?";
+const UNPREFERRED_PROMPT: &str = "Is there anyone you would rather not share with?";
+
+/// The names in a people list: one per line, skipping blank lines and
+/// lines starting with `#`.
+pub fn read_people(text: &str) -> Result<Vec<String>> {
+    let mut seen = HashSet::new();
+    let mut people = vec![];
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !seen.insert(line) {
+            bail!("{line} is listed twice");
+        }
+        people.push(line.to_string());
+    }
+    if people.is_empty() {
+        bail!("the people list is empty");
+    }
+    Ok(people)
+}
+
+/// A blank form for `people`.
+pub fn form(people: &[String], format: Format, style: Style) -> Vec<u8> {
+    match format {
+        Format::Md => markdown(people, style).into_bytes(),
+        Format::Html => html(people, style).into_bytes(),
+        Format::Docx => docx(people, style),
+    }
+}
+
+fn markdown(people: &[String], style: Style) -> String {
+    let mut text = format!("# {TITLE}\n\n{NAME_PROMPT}: ____________________\n\n");
+    match style {
+        Style::Grid => {
+            text.push_str(&format!(
+                "{GRID_HELP}\n\n| Name | Preferred | Unpreferred |\n| --- | :---: | :---: |\n"
+            ));
+            for person in people {
+                text.push_str(&format!("| {} | [ ] | [ ] |\n", person.replace('|', "\\|")));
+            }
+        }
+        Style::FreeText => {
+            text.push_str(&format!("{PREFERRED_PROMPT}\n\n"));
+            for i in 1..=PREFERRED_LINES {
+                text.push_str(&format!("{i}. ____________________\n"));
+            }
+            text.push_str(&format!("\n{UNPREFERRED_PROMPT}\n\n"));
+            for i in 1..=UNPREFERRED_LINES {
+                text.push_str(&format!("{i}. ____________________\n"));
+            }
+            text.push_str(&format!(
+                "\nPlease write names exactly as they appear here: {}\n",
+                people.join(", ")
+            ));
+        }
+    }
+    text
+}
+
+fn html(people: &[String], style: Style) -> String {
+    let options = people
+        .iter()
+        .map(|x| format!("<option>{}</option>", escape_html(x)))
+        .collect::<String>();
+    let mut body = format!(
+        "<label>{NAME_PROMPT}: <select name=\"name\" required><option value=\"\"></option>{options}</select></label>\n"
+    );
+    match style {
+        Style::Grid => {
+            body.push_str(&format!(
+                "<p>{GRID_HELP}</p>\n<table>\n<tr><th>Name</th><th>Preferred</th><th>Unpreferred</th></tr>\n"
+            ));
+            for person in people {
+                let person = escape_html(person);
+                body.push_str(&format!(
+                    "<tr><td>{person}</td>\
+                     <td><input type=\"checkbox\" name=\"preferred\" value=\"{person}\"></td>\
+                     <td><input type=\"checkbox\" name=\"unpreferred\" value=\"{person}\"></td></tr>\n"
+                ));
+            }
+            body.push_str("</table>\n");
+        }
+        Style::FreeText => {
+            let field = |name: &str| {
+                format!("<input name=\"{name}\" list=\"people\" autocomplete=\"off\"><br>\n")
+            };
+            body.push_str(&format!("<p>{PREFERRED_PROMPT}</p>\n"));
+            body.push_str(&field("preferred").repeat(PREFERRED_LINES));
+            body.push_str(&format!("<p>{UNPREFERRED_PROMPT}</p>\n"));
+            body.push_str(&field("unpreferred").repeat(UNPREFERRED_LINES));
+            body.push_str(&format!("<datalist id=\"people\">{options}</datalist>\n"));
+        }
+    }
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{TITLE}</title></head>\n\
+         <body>\n<h1>{TITLE}</h1>\n<form>\n{body}<p><button type=\"submit\">Submit</button></p>\n</form>\n\
+         </body>\n</html>\n"
+    )
+}
+
+/// A WordprocessingML paragraph, bold if `bold`.
+fn paragraph(text: &str, bold: bool) -> String {
+    format!(
+        "<w:p><w:r>{}<w:t xml:space=\"preserve\">{}</w:t></w:r></w:p>",
+        if bold { "<w:rPr><w:b/></w:rPr>" } else { "" },
+        escape_html(text)
+    )
+}
+
+fn cell(text: &str) -> String {
+    format!("<w:tc>{}</w:tc>", paragraph(text, false))
+}
+
+fn docx(people: &[String], style: Style) -> Vec<u8> {
+    let mut body = paragraph(TITLE, true);
+    body.push_str(&paragraph(
+        &format!("{NAME_PROMPT}: ____________________"),
+        false,
+    ));
+    match style {
+        Style::Grid => {
+            body.push_str(&paragraph(GRID_HELP, false));
+            body.push_str(
+                "<w:tbl><w:tblPr><w:tblW w:w=\"0\" w:type=\"auto\"/>\
+                 <w:tblBorders><w:top w:val=\"single\"/><w:left w:val=\"single\"/><w:bottom w:val=\"single\"/>\
+                 <w:right w:val=\"single\"/><w:insideH w:val=\"single\"/><w:insideV w:val=\"single\"/>\
+                 </w:tblBorders></w:tblPr>\
+                 <w:tblGrid><w:gridCol w:w=\"3600\"/><w:gridCol w:w=\"1800\"/><w:gridCol w:w=\"1800\"/></w:tblGrid>",
+            );
+            body.push_str(&format!(
+                "<w:tr>{}{}{}</w:tr>",
+                cell("Name"),
+                cell("Preferred"),
+                cell("Unpreferred")
+            ));
+            for person in people {
+                body.push_str(&format!(
+                    "<w:tr>{}{}{}</w:tr>",
+                    cell(person),
+                    cell("\u{2610}"),
+                    cell("\u{2610}")
+                ));
+            }
+            body.push_str("</w:tbl>");
+        }
+        Style::FreeText => {
+            body.push_str(&paragraph(PREFERRED_PROMPT, false));
+            for i in 1..=PREFERRED_LINES {
+                body.push_str(&paragraph(&format!("{i}. ____________________"), false));
+            }
+            body.push_str(&paragraph(UNPREFERRED_PROMPT, false));
+            for i in 1..=UNPREFERRED_LINES {
+                body.push_str(&paragraph(&format!("{i}. ____________________"), false));
+            }
+            body.push_str(&paragraph(
+                &format!(
+                    "Please write names exactly as they appear here: {}",
+                    people.join(", ")
+                ),
+                false,
+            ));
+        }
+    }
+    let document = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\">\
+         <w:body>{body}</w:body></w:document>"
+    );
+    zip(&[
+        (
+            "[Content_Types].xml",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+             <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+             <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+             <Override PartName=\"/word/document.xml\" \
+             ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml\"/>\
+             </Types>",
+        ),
+        (
+            "_rels/.rels",
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+             <Relationship Id=\"rId1\" \
+             Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument\" \
+             Target=\"word/document.xml\"/></Relationships>",
+        ),
+        ("word/document.xml", &document),
+    ])
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, byte| {
+        (0..8).fold(crc ^ u32::from(*byte), |crc, _| {
+            (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+/// A zip archive of `files`, stored without compression, which is all a
+/// .docx needs.
+fn zip(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut archive = vec![];
+    let mut directory = vec![];
+    for (name, contents) in files {
+        let (name, contents) = (name.as_bytes(), contents.as_bytes());
+        let offset = archive.len() as u32;
+        // Version 2.0, no flags, stored, dated 1980-01-01, then the CRC
+        // and sizes, which are the same compressed or not.
+        let mut fields = vec![];
+        fields.extend(20u16.to_le_bytes());
+        fields.extend([0u8; 6]);
+        fields.extend(0x0021u16.to_le_bytes());
+        fields.extend(crc32(contents).to_le_bytes());
+        fields.extend((contents.len() as u32).to_le_bytes());
+        fields.extend((contents.len() as u32).to_le_bytes());
+        fields.extend((name.len() as u16).to_le_bytes());
+        fields.extend(0u16.to_le_bytes());
+
+        archive.extend(0x0403_4b50u32.to_le_bytes());
+        archive.extend(&fields);
+        archive.extend(name);
+        archive.extend(contents);
+
+        directory.extend(0x0201_4b50u32.to_le_bytes());
+        directory.extend(20u16.to_le_bytes());
+        directory.extend(&fields);
+        // No comment, disk 0, no attributes, then where the file starts.
+        directory.extend([0u8; 10]);
+        directory.extend(offset.to_le_bytes());
+        directory.extend(name);
+    }
+    let start = archive.len() as u32;
+    let count = (files.len() as u16).to_le_bytes();
+    archive.extend(&directory);
+    archive.extend(0x0605_4b50u32.to_le_bytes());
+    archive.extend([0u8; 4]);
+    archive.extend(count);
+    archive.extend(count);
+    archive.extend((directory.len() as u32).to_le_bytes());
+    archive.extend(start.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+    archive
+}
@@ -0,0 +1,105 @@
+//! Swaps agreed between two people after the rooms are published: each
+//! takes the other's place, as long as no pin or other hard limit is
+//! broken by it.
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::fairness;
+use crate::history::Record;
+use crate::rules::{self, Rule};
+use crate::solver::{allowed, room_tier, within_cap, Problem, Solution};
+
+/// The rooms before and after a swap.
+pub struct Swap<'a> {
+    /// The saved rooms with the swap made.
+    pub record: Record,
+    pub before: Solution,
+    pub after: Solution,
+    /// Rules that held before the swap but don't after it.
+    pub broken: Vec<&'a Rule>,
+}
+
+fn room_index(record: &Record, name: &str) -> Result<usize> {
+    record
+        .rooms
+        .iter()
+        .position(|x| x.people.iter().any(|x| x == name))
+        .ok_or_else(|| anyhow!("{name} isn't in the saved rooms"))
+}
+
+/// Checks that everyone in a room may be there after a swap.
+fn check_room(record: &Record, index: usize, problem: &Problem) -> Result<()> {
+    let room = &record.rooms[index];
+    for (i, a) in room.people.iter().enumerate() {
+        if let Some(pin) = problem.pins.get(a).filter(|x| **x != room.name) {
+            bail!("{a} is pinned to \"{pin}\", so can't move to {}", room.name);
+        }
+        if let Some(venue) = problem
+            .venues
+            .get(a)
+            .filter(|x| problem.room_venues.get(&room.name) != Some(*x))
+        {
+            bail!("{a} must stay at {venue}, so can't move to {}", room.name);
+        }
+        if room.people.len() > 2 && !problem.allow_triple.contains(a) {
+            bail!("{} holds three, which {a} doesn't allow", room.name);
+        }
+        for b in &room.people[i + 1..] {
+            if !allowed(a, b, problem) {
+                bail!("{a} and {b} may not share a room");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Swaps `a` and `b` in the saved rooms, failing if that breaks a hard
+/// limit: a pin, a room of three or `max_unpreferred`.
+pub fn propose<'a>(record: &Record, a: &str, b: &str, problem: &'a Problem) -> Result<Swap<'a>> {
+    let before = record.solution(problem)?;
+    let (room_a, room_b) = (room_index(record, a)?, room_index(record, b)?);
+    if room_a == room_b {
+        bail!("{a} and {b} already share {}", record.rooms[room_a].name);
+    }
+    let mut swapped = record.clone();
+    for (index, from, to) in [(room_a, a, b), (room_b, b, a)] {
+        for person in &mut swapped.rooms[index].people {
+            if person == from {
+                *person = to.to_string();
+            }
+        }
+    }
+    for index in [room_a, room_b] {
+        check_room(&swapped, index, problem)?;
+        let room = &mut swapped.rooms[index];
+        room.annotate(problem);
+        if room.people.len() > 1 {
+            room.tier = room_tier(&room.people.iter().collect::<Vec<_>>(), problem);
+        }
+    }
+    let after = swapped.solution(problem)?;
+    if !within_cap(&after, problem) {
+        bail!(
+            "the swap would make {} unpreferred rooms, more than max_unpreferred = {}",
+            after.unpreferred,
+            problem.settings.max_unpreferred.unwrap_or(0)
+        );
+    }
+    if fairness::within(&before, problem) && !fairness::within(&after, problem) {
+        bail!("the swap would spread preferred rooms more unevenly than the [fairness] max_gap allows");
+    }
+    swapped.preferred = after.preferred;
+    swapped.accepted = after.accepted;
+    swapped.unpreferred = after.unpreferred;
+    let already = rules::broken(&before.result, problem);
+    let broken = rules::broken(&after.result, problem)
+        .into_iter()
+        .filter(|x| !already.iter().any(|y| std::ptr::eq(*x, *y)))
+        .collect();
+    Ok(Swap {
+        record: swapped,
+        before,
+        after,
+        broken,
+    })
+}
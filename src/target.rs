@@ -0,0 +1,224 @@
+//! Targets for `--target`, e.g. `preferred>=30,unpreferred==0`: the run
+//! keeps solving in batches until the rooms meet every condition or the
+//! time budget runs out.
+
+use anyhow::Result;
+use colored::Colorize;
+use std::fmt;
+use std::time::Instant;
+
+use crate::components;
+use crate::config::Strategy;
+use crate::logger;
+use crate::rng::Streams;
+use crate::solver::{compare, sample_budget, sample_solution, within_cap, Problem, Solution};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Counter {
+    Preferred,
+    Accepted,
+    Unpreferred,
+    Kept,
+    Score,
+}
+
+impl Counter {
+    const ALL: [(Self, &'static str); 5] = [
+        (Self::Preferred, "preferred"),
+        (Self::Accepted, "accepted"),
+        (Self::Unpreferred, "unpreferred"),
+        (Self::Kept, "kept"),
+        (Self::Score, "score"),
+    ];
+
+    fn of(self, solution: &Solution) -> f64 {
+        match self {
+            Self::Preferred => solution.preferred as f64,
+            Self::Accepted => solution.accepted as f64,
+            Self::Unpreferred => solution.unpreferred as f64,
+            Self::Kept => solution.kept as f64,
+            Self::Score => solution.score,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        Self::ALL.iter().find(|x| x.0 == self).unwrap().1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    AtLeast,
+    AtMost,
+    Equal,
+    Above,
+    Below,
+}
+
+impl Op {
+    // Two-character operators first, so `>=` isn't read as `>`.
+    const ALL: [(Self, &'static str); 5] = [
+        (Self::AtLeast, ">="),
+        (Self::AtMost, "<="),
+        (Self::Equal, "=="),
+        (Self::Above, ">"),
+        (Self::Below, "<"),
+    ];
+
+    fn holds(self, value: f64, bound: f64) -> bool {
+        match self {
+            Self::AtLeast => value >= bound,
+            Self::AtMost => value <= bound,
+            Self::Equal => value == bound,
+            Self::Above => value > bound,
+            Self::Below => value < bound,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        Self::ALL.iter().find(|x| x.0 == self).unwrap().1
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Condition {
+    counter: Counter,
+    op: Op,
+    bound: f64,
+}
+
+/// Conditions on a solution's counters, all of which must hold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target(Vec<Condition>);
+
+impl Target {
+    /// Reads comma-separated conditions such as `preferred>=30`, on
+    /// preferred, accepted, unpreferred, kept or score, with `>=`, `<=`,
+    /// `==`, `>` or `<`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut conditions = vec![];
+        for part in text.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+            let (i, op, symbol) = Op::ALL
+                .iter()
+                .find_map(|(op, symbol)| Some((part.find(symbol)?, *op, *symbol)))
+                .ok_or_else(|| format!("{part:?} has no >=, <=, ==, > or <"))?;
+            let name = part[..i].trim();
+            let counter = Counter::ALL
+                .iter()
+                .find(|x| x.1 == name)
+                .ok_or_else(|| {
+                    format!("unknown counter {name:?}, expected preferred, accepted, unpreferred, kept or score")
+                })?
+                .0;
+            let bound = part[i + symbol.len()..]
+                .trim()
+                .parse::<f64>()
+                .map_err(|e| format!("{part:?}: {e}"))?;
+            conditions.push(Condition { counter, op, bound });
+        }
+        if conditions.is_empty() {
+            return Err("the target has no conditions".to_string());
+        }
+        Ok(Self(conditions))
+    }
+
+    pub fn met(&self, solution: &Solution) -> bool {
+        self.unmet(solution).is_empty()
+    }
+
+    /// The conditions `solution` doesn't meet, as written in the target.
+    pub fn unmet(&self, solution: &Solution) -> Vec<String> {
+        self.0
+            .iter()
+            .filter(|x| !x.op.holds(x.counter.of(solution), x.bound))
+            .map(ToString::to_string)
+            .collect()
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.counter.name(),
+            self.op.symbol(),
+            self.bound
+        )
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts = self.0.iter().map(ToString::to_string).collect::<Vec<_>>();
+        write!(f, "{}", parts.join(","))
+    }
+}
+
+/// The better of two solutions: one meeting the target beats one that
+/// doesn't, then one within `max_unpreferred`, then the usual ranking.
+fn better(a: Solution, b: Solution, target: &Target, problem: &Problem) -> Solution {
+    let key = |x: &Solution| (target.met(x), within_cap(x, problem));
+    let (key_a, key_b) = (key(&a), key(&b));
+    if key_a > key_b || key_a == key_b && compare(&a, &b, problem.settings.ranking).is_gt() {
+        a
+    } else {
+        b
+    }
+}
+
+/// Solves again in batches, each with fresh randomness, until the best
+/// solution so far meets `target` or `deadline` passes. Batches alternate
+/// between the configured strategy and the other one, as clustered
+/// preferences suit components and scattered ones suit sampling. Returns
+/// the best solution and the number of batches, counting `first`.
+pub fn pursue(
+    problem: &Problem,
+    first: Solution,
+    target: &Target,
+    max_memory: u64,
+    streams: &mut Streams,
+    deadline: Instant,
+) -> Result<(Solution, u64)> {
+    let strategies = match problem.settings.solver {
+        Strategy::Components => [Strategy::Sample, Strategy::Components],
+        // Exact solutions can't be beaten, so sampling is only there to
+        // try something else.
+        Strategy::Sample | Strategy::Exact | Strategy::Auto => {
+            [Strategy::Components, Strategy::Sample]
+        }
+    };
+    let mut best = first;
+    let mut batches = 1;
+    while !target.met(&best) && Instant::now() < deadline {
+        let strategy = strategies[(batches as usize - 1) % 2];
+        let mut forked = streams.fork("target", batches);
+        logger::Logger::info(format!(
+            "{} {} {} {}",
+            "Target not met yet, starting batch".truecolor(100, 100, 100),
+            (batches + 1).to_string().truecolor(55, 80, 140),
+            "with".truecolor(100, 100, 100),
+            match strategy {
+                Strategy::Components => "components",
+                _ => "sample",
+            }
+            .truecolor(100, 100, 100),
+        ))?
+        .end();
+        let candidate = match strategy {
+            Strategy::Components => {
+                let log = logger::Logger::info(
+                    "Solving each preference cluster".truecolor(100, 100, 100),
+                )?;
+                let budget = sample_budget(problem, max_memory);
+                let solution = components::solve(problem, budget, &mut forked, false)?;
+                log.end();
+                solution
+            }
+            _ => sample_solution(problem, max_memory, &mut forked, None, None)?.0,
+        };
+        best = better(candidate, best, target, problem);
+        batches += 1;
+    }
+    Ok((best, batches))
+}
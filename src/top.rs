@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use crate::config::Ranking;
+use crate::solver::{compare, Compact};
+
+/// A solution along with how many times it was generated.
+pub struct Distinct {
+    pub solution: Compact,
+    pub count: usize,
+}
+
+/// The same rooms written the same way however they were found: each pair
+/// in index order, and the pairs sorted.
+pub fn canonical(rooms: &[[u16; 2]]) -> Vec<[u16; 2]> {
+    let mut pairs = rooms
+        .iter()
+        .map(|&[a, b]| [a.min(b), a.max(b)])
+        .collect::<Vec<_>>();
+    pairs.sort();
+    pairs
+}
+
+/// Ranks `solutions` best first (the most often generated first among
+/// equals), merging ones that only differ in room
+/// order or pair order unless `keep_duplicates` is set.
+pub fn distinct(solutions: Vec<Compact>, ranking: Ranking, keep_duplicates: bool) -> Vec<Distinct> {
+    let mut distinct = vec![];
+    let mut seen = HashMap::<Vec<[u16; 2]>, usize>::new();
+    for solution in solutions {
+        if !keep_duplicates {
+            let key = canonical(&solution.rooms);
+            if let Some(&i) = seen.get(&key) {
+                let existing: &mut Distinct = &mut distinct[i];
+                existing.count += 1;
+                continue;
+            }
+            seen.insert(key, distinct.len());
+        }
+        distinct.push(Distinct { solution, count: 1 });
+    }
+    distinct.sort_by(|a, b| compare(&b.solution, &a.solution, ranking).then(b.count.cmp(&a.count)));
+    distinct
+}
@@ -0,0 +1,220 @@
+//! What-if runs for `tune --grid`: the same config solved under every
+//! combination of weights in a grid, with what each combination gave
+//! written to a CSV file, so weights can be picked from how they do on
+//! real data rather than by feel.
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::config::Ranking;
+use crate::objective::Weights;
+use crate::solver::{flexible_placements, is_disappointed, occupants, Problem, Solution};
+
+/// The values to try for each weight, e.g. `tag_weight = [0, 0.5, 1]`.
+/// Anything left out keeps its value from the config.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Grid {
+    #[serde(default)]
+    pub ranking: Vec<Ranking>,
+    #[serde(default)]
+    pub keep_current_weight: Vec<f64>,
+    #[serde(default)]
+    pub stale_penalty: Vec<f64>,
+    #[serde(default)]
+    pub no_link_penalty: Vec<f64>,
+    #[serde(default)]
+    pub disappointment_penalty: Vec<f64>,
+    #[serde(default)]
+    pub tag_weight: Vec<f64>,
+    #[serde(default)]
+    pub theme_weight: Vec<f64>,
+    #[serde(default)]
+    pub rating_weight: Vec<f64>,
+    #[serde(default)]
+    pub popularity_penalty: Vec<f64>,
+    #[serde(default)]
+    pub size_pref_penalty: Vec<f64>,
+}
+
+/// One point of the grid: the weights it sets, and their values by name
+/// for the CSV file.
+pub struct Combination {
+    pub weights: Weights,
+    pub values: Vec<(&'static str, String)>,
+}
+
+/// Sets one soft weight.
+type Setter = fn(&mut Weights, f64);
+
+/// Every combination so far, each extended with every one of `values`.
+fn extend<T: Copy>(
+    combinations: Vec<Combination>,
+    name: &'static str,
+    values: &[T],
+    label: impl Fn(T) -> String,
+    set: impl Fn(&mut Weights, T),
+) -> Vec<Combination> {
+    if values.is_empty() {
+        return combinations;
+    }
+    combinations
+        .iter()
+        .flat_map(|combination| {
+            values.iter().map(|value| {
+                let mut weights = combination.weights.clone();
+                set(&mut weights, *value);
+                let mut values = combination.values.clone();
+                values.push((name, label(*value)));
+                Combination { weights, values }
+            })
+        })
+        .collect()
+}
+
+impl Grid {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).map_err(|e| anyhow!("{}: {e}", path.display()))?;
+        toml::from_str(&text).map_err(|e| anyhow!("{}: {e}", path.display()))
+    }
+
+    /// Every combination of the values in the grid, varying the last
+    /// weight fastest.
+    pub fn combinations(&self) -> Result<Vec<Combination>> {
+        let mut combinations = vec![Combination {
+            weights: Weights::default(),
+            values: vec![],
+        }];
+        combinations = extend(
+            combinations,
+            "ranking",
+            &self.ranking,
+            |x| {
+                serde_json::to_value(x)
+                    .ok()
+                    .and_then(|x| x.as_str().map(str::to_string))
+                    .unwrap_or_default()
+            },
+            |weights, x| weights.ranking = Some(x),
+        );
+        let weights: [(&str, &[f64], Setter); 9] = [
+            ("keep_current_weight", &self.keep_current_weight, |w, x| {
+                w.keep_current_weight = Some(x)
+            }),
+            ("stale_penalty", &self.stale_penalty, |w, x| {
+                w.stale_penalty = Some(x)
+            }),
+            ("no_link_penalty", &self.no_link_penalty, |w, x| {
+                w.no_link_penalty = Some(x)
+            }),
+            (
+                "disappointment_penalty",
+                &self.disappointment_penalty,
+                |w, x| w.disappointment_penalty = Some(x),
+            ),
+            ("tag_weight", &self.tag_weight, |w, x| {
+                w.tag_weight = Some(x)
+            }),
+            ("theme_weight", &self.theme_weight, |w, x| {
+                w.theme_weight = Some(x)
+            }),
+            ("rating_weight", &self.rating_weight, |w, x| {
+                w.rating_weight = Some(x)
+            }),
+            ("popularity_penalty", &self.popularity_penalty, |w, x| {
+                w.popularity_penalty = Some(x)
+            }),
+            ("size_pref_penalty", &self.size_pref_penalty, |w, x| {
+                w.size_pref_penalty = Some(x)
+            }),
+        ];
+        for (name, values, set) in weights {
+            combinations = extend(combinations, name, values, |x| x.to_string(), set);
+        }
+        if combinations[0].values.is_empty() {
+            bail!("the grid doesn't list any weights to try");
+        }
+        Ok(combinations)
+    }
+}
+
+/// What one combination of weights gave.
+pub struct Metrics {
+    pub preferred: usize,
+    pub accepted: usize,
+    pub unpreferred: usize,
+    pub kept: usize,
+    /// People who listed someone as preferred but got none of them.
+    pub disappointed: usize,
+    /// See [`flexible_placements`].
+    pub flexible_placements: usize,
+    pub score: f64,
+}
+
+impl Metrics {
+    pub fn new(solution: &Solution, problem: &Problem) -> Self {
+        let disappointed = occupants(solution, problem)
+            .iter()
+            .map(|(_, people)| {
+                people
+                    .iter()
+                    .filter(|person| {
+                        people
+                            .iter()
+                            .filter(|x| x != person)
+                            .all(|x| is_disappointed(person, x, problem))
+                    })
+                    .count()
+            })
+            .sum();
+        Self {
+            preferred: solution.preferred,
+            accepted: solution.accepted,
+            unpreferred: solution.unpreferred,
+            kept: solution.kept,
+            disappointed,
+            flexible_placements: flexible_placements(solution, problem),
+            score: solution.score,
+        }
+    }
+}
+
+/// Writes a row per combination: the values it set, then what it gave.
+pub fn write(path: &Path, results: &[(Combination, Metrics)]) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    let Some((first, _)) = results.first() else {
+        return Ok(());
+    };
+    let mut header = first.values.iter().map(|x| x.0).collect::<Vec<_>>();
+    header.extend([
+        "preferred",
+        "accepted",
+        "unpreferred",
+        "kept",
+        "disappointed",
+        "flexible_placements",
+        "score",
+    ]);
+    writer.write_record(header)?;
+    for (combination, metrics) in results {
+        let mut row = combination
+            .values
+            .iter()
+            .map(|x| x.1.clone())
+            .collect::<Vec<_>>();
+        row.extend([
+            metrics.preferred.to_string(),
+            metrics.accepted.to_string(),
+            metrics.unpreferred.to_string(),
+            metrics.kept.to_string(),
+            metrics.disappointed.to_string(),
+            metrics.flexible_placements.to_string(),
+            metrics.score.to_string(),
+        ]);
+        writer.write_record(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
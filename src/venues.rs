@@ -0,0 +1,88 @@
+//! Trips split across several venues, e.g. two hotels, each with its own
+//! rooms under `[venues]`. People who must stay at a venue (from their
+//! `venue`, or the room they're pinned to) only share with others who may
+//! stay there too, and their rooms only go at that venue; everyone else's
+//! rooms go wherever there's space, at their `preferred_venue` where the
+//! rooms allow.
+
+use anyhow::{bail, Result};
+use indexmap::IndexMap;
+
+use crate::solver::{occupants, Problem, Solution};
+
+/// The venue a room of `a` and `b` must be at, if either must stay at one.
+pub fn venue<'a>(a: &str, b: &str, problem: &'a Problem) -> Option<&'a String> {
+    problem.venues.get(a).or_else(|| problem.venues.get(b))
+}
+
+/// How many of the rooms in `result` must be at each venue, along with how
+/// many rooms each venue has.
+fn demand<'a>(
+    result: &[(String, String)],
+    problem: &'a Problem,
+) -> IndexMap<&'a str, (usize, usize)> {
+    let mut demand = IndexMap::<&str, (usize, usize)>::new();
+    for venue in problem.room_venues.values() {
+        demand.entry(venue).or_default().1 += 1;
+    }
+    for (a, b) in result {
+        if let Some(venue) = venue(a, b, problem) {
+            demand.entry(venue).or_default().0 += 1;
+        }
+    }
+    demand
+}
+
+/// Whether every venue has enough rooms for the pairs in `result` that
+/// must stay there.
+pub fn fits(result: &[(String, String)], problem: &Problem) -> bool {
+    problem.venues.is_empty()
+        || demand(result, problem)
+            .values()
+            .all(|(needed, rooms)| needed <= rooms)
+}
+
+/// Fails if the pairs in `result` need more rooms at a venue than it has,
+/// e.g. because a solver other than the sample solver doesn't check.
+pub fn check(result: &[(String, String)], problem: &Problem) -> Result<()> {
+    if problem.venues.is_empty() {
+        return Ok(());
+    }
+    for (venue, (needed, rooms)) in demand(result, problem) {
+        if needed > rooms {
+            bail!("the best solution found needs {needed} rooms at {venue}, but it only has {rooms}; try a larger solutions budget or the sample solver");
+        }
+    }
+    Ok(())
+}
+
+/// How many of `a` and `b` would rather stay somewhere other than where
+/// `room` is.
+pub fn misses(a: &str, b: &str, room: &str, problem: &Problem) -> usize {
+    let here = problem.room_venues.get(room);
+    [a, b]
+        .into_iter()
+        .filter(|x| {
+            problem
+                .preferred_venues
+                .get(*x)
+                .is_some_and(|x| Some(x) != here)
+        })
+        .count()
+}
+
+/// How many people with a `preferred_venue` stay there in `solution`, out
+/// of how many have one.
+pub fn preferences_met(solution: &Solution, problem: &Problem) -> (usize, usize) {
+    let met = occupants(solution, problem)
+        .iter()
+        .flat_map(|(room, people)| people.iter().map(move |x| (room, x)))
+        .filter(|(room, person)| {
+            problem
+                .preferred_venues
+                .get(person.as_str())
+                .is_some_and(|x| problem.room_venues.get(*room) == Some(x))
+        })
+        .count();
+    (met, problem.preferred_venues.len())
+}
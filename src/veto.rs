@@ -0,0 +1,139 @@
+//! The draft-then-confirm workflow: `--slips` writes each person a slip
+//! with their draft room and roommate, and whoever can't accept their
+//! roommate says so in a vetoes file, whose pairings the next run rules
+//! out.
+
+use anyhow::{anyhow, bail, Result};
+use clap::ValueEnum;
+use indexmap::{IndexMap, IndexSet};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::names::{Matcher, Unmatched};
+use crate::solver::roommates;
+
+/// What confirmation slips are written as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SlipFormat {
+    Text,
+    Html,
+}
+
+/// A file name for `person`'s slip, keeping only characters that are safe
+/// in file names everywhere.
+fn file_name(person: &str, format: SlipFormat) -> String {
+    let stem = person
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    match format {
+        SlipFormat::Text => format!("{stem}.txt"),
+        SlipFormat::Html => format!("{stem}.html"),
+    }
+}
+
+/// Escapes `text` for HTML (or XML) text and attribute values.
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn slip(person: &str, room: &str, partner: &str, format: SlipFormat) -> String {
+    match format {
+        SlipFormat::Text => format!(
+            "Draft room for {person}\n\n\
+             Room: {room}\n\
+             Roommate: {partner}\n\n\
+             This is a draft. If you can't share with {partner}, let the \
+             organisers know and the rooms will be worked out again without \
+             that pairing.\n"
+        ),
+        SlipFormat::Html => {
+            let (person, room, partner) =
+                (escape_html(person), escape_html(room), escape_html(partner));
+            format!(
+                "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Draft room for {person}</title></head>\n\
+                 <body>\n<h1>Draft room for {person}</h1>\n\
+                 <p>Room: <strong>{room}</strong><br>Roommate: <strong>{partner}</strong></p>\n\
+                 <p>This is a draft. If you can't share with {partner}, let the organisers \
+                 know and the rooms will be worked out again without that pairing.</p>\n\
+                 </body>\n</html>\n"
+            )
+        }
+    }
+}
+
+/// Writes a slip for everyone in `placed` into `dir`.
+pub fn write_slips(
+    placed: &[(String, Vec<&String>)],
+    dir: &Path,
+    format: SlipFormat,
+) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let mut written = HashSet::new();
+    for (room, people) in placed {
+        for (person, partner) in roommates(people) {
+            let name = file_name(person, format);
+            if !written.insert(name.clone()) {
+                bail!("{person}'s slip would overwrite another one named {name}");
+            }
+            fs::write(dir.join(name), slip(person, room, &partner, format))?;
+        }
+    }
+    Ok(())
+}
+
+/// One row of the vetoes file.
+#[derive(Debug, Deserialize)]
+struct Veto {
+    name: String,
+    vetoed: String,
+}
+
+/// Vetoed pairings, both ways round.
+pub type Vetoes = IndexMap<String, IndexSet<String>>;
+
+/// Reads a CSV file with `name` and `vetoed` columns, matching names
+/// against the config with `matcher`, and returns everyone each person
+/// may not share with (both ways round), along with any names that
+/// didn't match exactly one person.
+pub fn read_vetoes(path: &Path, matcher: &Matcher) -> Result<(Vetoes, Vec<Unmatched>)> {
+    let mut vetoed = Vetoes::new();
+    let mut unmatched = vec![];
+    for (i, row) in csv::Reader::from_path(path)?
+        .deserialize::<Veto>()
+        .enumerate()
+    {
+        let veto = row.map_err(|e| anyhow!("{}: {e}", path.display()))?;
+        let (name, other) = match (
+            matcher.find(&veto.name, i + 2),
+            matcher.find(&veto.vetoed, i + 2),
+        ) {
+            (Ok(name), Ok(other)) => (name, other),
+            (name, other) => {
+                unmatched.extend(name.err());
+                unmatched.extend(other.err());
+                continue;
+            }
+        };
+        vetoed
+            .entry(name.clone())
+            .or_default()
+            .insert(other.clone());
+        vetoed
+            .entry(other.clone())
+            .or_default()
+            .insert(name.clone());
+    }
+    Ok((vetoed, unmatched))
+}
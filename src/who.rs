@@ -0,0 +1,104 @@
+use anyhow::{anyhow, bail, Result};
+use colored::Colorize;
+use std::path::Path;
+
+use crate::storage::Storage;
+use crate::{collate, history, names, solver};
+
+/// Prints everything the saved solution and history know about one person:
+/// their room and roommate, how well that pairing fits, and who they have
+/// shared with before, following them by `id` through any renames.
+pub fn who(
+    storage: &Storage,
+    query: &str,
+    solution: Option<&Path>,
+    history: Option<&Path>,
+) -> Result<()> {
+    let records = match history {
+        Some(path) => history::read(storage, path)?,
+        None => vec![],
+    };
+    let latest = match solution {
+        Some(path) => history::Record::load(storage, path)?,
+        None => records
+            .last()
+            .cloned()
+            .ok_or_else(|| anyhow!("No saved solution, pass --solution or --history"))?,
+    };
+
+    let mut everyone = records
+        .iter()
+        .chain([&latest])
+        .flat_map(|x| x.rooms.iter().flat_map(|x| x.people.iter().cloned()))
+        .collect::<Vec<_>>();
+    collate::sort(&mut everyone);
+    everyone.dedup();
+
+    let name = match names::fuzzy_find(query, &everyone).as_slice() {
+        [] => bail!("No one matching \"{query}\" was found"),
+        [name] => (*name).clone(),
+        several => bail!(
+            "\"{query}\" could mean any of: {}",
+            several
+                .iter()
+                .map(|x| x.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+
+    let identity = [&latest]
+        .into_iter()
+        .chain(records.iter().rev())
+        .find(|x| x.room_of(x.identity(&name)).is_some())
+        .map_or(name.clone(), |x| x.identity(&name).to_string());
+
+    println!("{}", name.green());
+    match latest.room_of(&identity) {
+        Some(room) => {
+            let partners = room
+                .people
+                .iter()
+                .filter(|x| latest.identity(x) != identity)
+                .map(|x| x.blue().to_string())
+                .collect::<Vec<_>>();
+            println!(
+                "       {} with {} ({}) on {}{}",
+                room.name,
+                partners.join(" & "),
+                room.tier,
+                latest.date,
+                solver::notes_suffix(room.medical, &room.notes)
+            );
+        }
+        None => println!("       not in the latest solution"),
+    }
+
+    let past = records
+        .iter()
+        .rev()
+        .filter_map(|record| {
+            let room = record.room_of(&identity)?;
+            let partners = room
+                .people
+                .iter()
+                .filter(|x| record.identity(x) != identity)
+                .map(|x| x.as_str())
+                .collect::<Vec<_>>()
+                .join(" & ");
+            Some(format!(
+                "{} ({}, {})",
+                partners.blue(),
+                record.date,
+                room.tier
+            ))
+        })
+        .collect::<Vec<_>>();
+    if !past.is_empty() {
+        println!("       past partners:");
+        for line in past {
+            println!("         {line}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use std::thread;
+
+use room_matcher::rng::Streams;
+use room_matcher::solver::{self, Problem, Solution};
+use room_matcher::{components, config, gen, logger};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+fn problem() -> Problem {
+    let doc = gen::generate(&gen::Options {
+        people: 30,
+        density: 0.3,
+        clusters: 4,
+        avoid_density: 0.05,
+        mutual: 0.5,
+        seed: 7,
+    })
+    .unwrap();
+    let mut config = config::parse(&doc).unwrap();
+    config.config.solutions = 1_000;
+    Problem::from_config(config, 0).unwrap()
+}
+
+fn sample(problem: &Problem, seed: u64) -> Solution {
+    solver::sample_solution(problem, u64::MAX, &mut Streams::new(seed), None, None)
+        .unwrap()
+        .0
+}
+
+fn by_components(problem: &Problem, seed: u64) -> Solution {
+    components::solve(problem, 200, &mut Streams::new(seed), false).unwrap()
+}
+
+#[test]
+fn problem_is_send_and_sync() {
+    assert_send_sync::<Problem>();
+    assert_send_sync::<Solution>();
+}
+
+#[test]
+fn concurrent_solves_match_sequential_ones() {
+    logger::set_quiet(true);
+    let problem = problem();
+    let seeds = [1, 2, 3, 1];
+    for strategy in [sample, by_components] {
+        let expected = seeds
+            .iter()
+            .map(|seed| strategy(&problem, *seed).result)
+            .collect::<Vec<_>>();
+        let results = thread::scope(|scope| {
+            let handles = seeds
+                .iter()
+                .map(|seed| scope.spawn(|| strategy(&problem, *seed).result))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|x| x.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(results, expected);
+    }
+}